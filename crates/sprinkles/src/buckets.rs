@@ -14,6 +14,7 @@ use crate::{
     contexts::ScoopContext,
     git::{self, Repo},
     packages::{self, CreateManifest, InstallManifest, Manifest, SearchMode},
+    proxy::Proxy,
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -43,6 +44,7 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// A bucket
 pub struct Bucket {
     bucket_path: PathBuf,
+    proxy: Proxy,
 }
 
 impl Bucket {
@@ -50,8 +52,11 @@ impl Bucket {
     ///
     /// # Errors
     /// - Bucket does not exist
-    pub fn from_name<C>(ctx: &impl ScoopContext<C>, name: impl AsRef<Path>) -> Result<Self> {
-        Self::from_path(ctx.buckets_path().join(name))
+    pub fn from_name(
+        ctx: &impl ScoopContext<config::Scoop>,
+        name: impl AsRef<Path>,
+    ) -> Result<Self> {
+        Ok(Self::from_path(ctx.buckets_path().join(name))?.with_config(ctx.config()))
     }
 
     /// Open given path as a bucket
@@ -62,7 +67,10 @@ impl Bucket {
         let bucket_path = path.as_ref().to_path_buf();
 
         if bucket_path.exists() {
-            Ok(Self { bucket_path })
+            Ok(Self {
+                bucket_path,
+                proxy: Proxy::default(),
+            })
         } else {
             Err(Error::InvalidBucket(path.as_ref().to_path_buf()))
         }
@@ -73,8 +81,8 @@ impl Bucket {
     /// # Errors
     /// - Any listed or provided bucket is invalid
     /// - Unable to read the bucket directory
-    pub fn one_or_all<C>(
-        ctx: &impl ScoopContext<C>,
+    pub fn one_or_all(
+        ctx: &impl ScoopContext<config::Scoop>,
         name: Option<impl AsRef<Path>>,
     ) -> Result<Vec<Self>> {
         if let Some(name) = name {
@@ -111,17 +119,32 @@ impl Bucket {
         &self.bucket_path
     }
 
+    #[must_use]
+    /// Use the proxy configured in the given Scoop config when interacting with the bucket's remote
+    ///
+    /// Buckets opened through a context already use that context's config
+    pub fn with_config(mut self, config: &config::Scoop) -> Self {
+        self.proxy = Proxy::from_config(config);
+        self
+    }
+
+    #[must_use]
+    /// Gets the proxy used when interacting with the bucket's remote
+    pub fn proxy(&self) -> &Proxy {
+        &self.proxy
+    }
+
     /// Gets all buckets
     ///
     /// # Errors
     /// - Was unable to read the bucket directory
     /// - Any listed bucket is invalid
-    pub fn list_all<C>(ctx: &impl ScoopContext<C>) -> Result<Vec<Bucket>> {
+    pub fn list_all(ctx: &impl ScoopContext<config::Scoop>) -> Result<Vec<Bucket>> {
         let bucket_dir = std::fs::read_dir(ctx.buckets_path())?;
 
         bucket_dir
             .filter(|entry| entry.as_ref().is_ok_and(|entry| entry.path().is_dir()))
-            .map(|entry| Self::from_path(entry?.path()))
+            .map(|entry| Ok(Self::from_path(entry?.path())?.with_config(ctx.config())))
             .collect()
     }
 
@@ -199,6 +222,24 @@ impl Bucket {
         Manifest::from_path(manifest_path).map(|manifest| manifest.with_bucket(self))
     }
 
+    /// Find the manifest with the highest version for the given package name across all local buckets
+    ///
+    /// Returns [`None`] if no bucket contains a valid manifest for the package
+    ///
+    /// # Errors
+    /// - Unable to read the bucket directory
+    pub fn newest_manifest(
+        ctx: &impl ScoopContext<config::Scoop>,
+        name: impl AsRef<str>,
+    ) -> Result<Option<Manifest>> {
+        let name = name.as_ref();
+
+        Ok(Self::list_all(ctx)?
+            .into_iter()
+            .filter_map(|bucket| bucket.get_manifest(name).ok())
+            .max_by(|a, b| a.version.cmp(&b.version)))
+    }
+
     /// List all matches for the given pattern
     ///
     /// # Errors
@@ -317,33 +358,109 @@ impl Bucket {
 
 #[cfg(test)]
 mod tests {
-    use crate::contexts::User;
+    use itertools::Itertools;
+
+    use crate::contexts::Fixture;
 
     use super::*;
 
+    /// Write and commit a file to the repository
+    fn commit_file(repo: &git2::Repository, path: &str, contents: &str) {
+        let file = repo.workdir().unwrap().join(path);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(file, contents).unwrap();
+
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("sfsu", "sfsu@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            &format!("Update {path}"),
+            &tree,
+            &parent.iter().collect_vec(),
+        )
+        .unwrap();
+    }
+
+    /// Clone a local upstream bucket into a fixture, then pull a new commit from it
+    fn test_bucket_update(name: &str) {
+        let dir = tempfile::tempdir().unwrap();
+
+        let upstream = git2::Repository::init(dir.path().join("upstream")).unwrap();
+        commit_file(&upstream, "bucket/app.json", r#"{"version":"1.0.0"}"#);
+
+        let ctx = Fixture::builder(dir.path().join("scoop")).build().unwrap();
+        git2::Repository::clone(
+            upstream.workdir().unwrap().to_str().unwrap(),
+            ctx.buckets_path().join(name),
+        )
+        .unwrap();
+
+        commit_file(&upstream, "bucket/app.json", r#"{"version":"2.0.0"}"#);
+
+        let bucket = Bucket::from_name(&ctx, name).unwrap();
+        bucket.open_repo().unwrap().pull(None).unwrap();
+
+        assert_eq!(
+            bucket.get_manifest("app").unwrap().version.as_str(),
+            "2.0.0"
+        );
+    }
+
     #[test]
     fn test_list_all_buckets() {
-        let ctx = User::new();
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = Fixture::builder(dir.path())
+            .bucket("main")
+            .bucket_manifest("extras", "sfsu", r#"{"version":"1.0.0"}"#)
+            .build()
+            .unwrap();
+
         let buckets = Bucket::list_all(&ctx).unwrap();
 
-        assert!(!buckets.is_empty());
+        assert_eq!(
+            buckets.iter().map(Bucket::name).sorted().collect_vec(),
+            vec!["extras", "main"]
+        );
     }
 
     #[test]
-    fn test_main_bucket_update() {
-        let ctx = User::new();
-
-        let bucket = Bucket::from_name(&ctx, "main").unwrap();
+    fn test_buckets_use_context_proxy() {
+        let dir = tempfile::tempdir().unwrap();
+        let proxy = Proxy::new(None, None, "proxy.example.com".to_string(), 8080);
+        let ctx = Fixture::builder(dir.path())
+            .config(config::Scoop {
+                proxy: Some(proxy.clone()),
+                ..Default::default()
+            })
+            .bucket("main")
+            .build()
+            .unwrap();
+
+        assert_eq!(Bucket::from_name(&ctx, "main").unwrap().proxy(), &proxy);
+        assert_eq!(Bucket::list_all(&ctx).unwrap()[0].proxy(), &proxy);
+        assert_eq!(
+            Bucket::from_path(ctx.buckets_path().join("main"))
+                .unwrap()
+                .proxy(),
+            &Proxy::default()
+        );
+    }
 
-        bucket.open_repo().unwrap().pull(None).unwrap();
+    #[test]
+    fn test_main_bucket_update() {
+        test_bucket_update("main");
     }
 
     #[test]
     fn test_extras_bucket_update() {
-        let ctx = User::new();
-
-        let bucket = Bucket::from_name(&ctx, "extras").unwrap();
-
-        bucket.open_repo().unwrap().pull(None).unwrap();
+        test_bucket_update("extras");
     }
 }
@@ -5,23 +5,36 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use bytes::BytesMut;
 use digest::Digest;
-use futures::{Stream, StreamExt, TryStreamExt};
+use futures::StreamExt;
 use indicatif::{MultiProgress, ProgressBar};
-use reqwest::{Response, StatusCode};
-use tokio::io::AsyncWriteExt;
+use reqwest::{header, Response, StatusCode};
+use tokio::{
+    fs::{File, OpenOptions},
+    io::AsyncWriteExt,
+    sync::OwnedSemaphorePermit,
+};
 use tokio_util::codec::{BytesCodec, FramedRead};
 
 use crate::{
+    config::{self, mirror::Mirror},
     hash::{url_ext::UrlExt, Hash, HashType},
-    let_chain,
-    packages::{models::manifest::TOrArrayOfTs, Manifest},
+    let_chain, offline,
+    packages::{downloading::DownloadUrl, models::manifest::TOrArrayOfTs, Manifest},
     progress,
-    requests::ClientLike,
+    requests::{self, ClientLike},
     Architecture,
 };
 
+pub mod github;
+pub mod limits;
+pub mod retry;
+pub mod segments;
+
+use limits::DownloadLimits;
+use retry::RetryPolicy;
+use segments::SegmentPolicy;
+
 #[derive(Debug, thiserror::Error)]
 #[allow(missing_docs)]
 /// Cache error
@@ -34,10 +47,18 @@ pub enum Error {
     ErrorCode(StatusCode),
     #[error("Missing download url in manifest")]
     MissingDownloadUrl,
+    #[error("Manifest has {urls} download urls, but {hashes} hashes")]
+    HashCountMismatch { urls: usize, hashes: usize },
     #[error("Non-utf8 file name")]
     InvalidFileName,
     #[error("Missing parts in output file name")]
     MissingParts,
+    #[error("Connection closed before the download segment was complete")]
+    IncompleteSegment,
+    #[error("Invalid GitHub API response: {0}")]
+    GithubApi(#[from] serde_json::Error),
+    #[error("File is not cached, and cannot be downloaded while offline")]
+    Offline,
 }
 
 #[derive(Debug, Clone)]
@@ -90,6 +111,8 @@ pub struct DownloadResult {
     /// The computed hash
     pub computed_hash: Hash,
     /// The hash stored in the manifest
+    ///
+    /// If the handle was opened without a hash, this is the computed hash
     pub actual_hash: Hash,
 }
 
@@ -102,7 +125,14 @@ pub struct Handle {
     /// The cache output file path
     cache_path: PathBuf,
     hash_type: HashType,
-    actual_hash: Hash,
+    actual_hash: Option<Hash>,
+    retry: RetryPolicy,
+    mirrors: Vec<Mirror>,
+    segments: SegmentPolicy,
+    github_token: Option<String>,
+    github_api_url: String,
+    cookie: Option<String>,
+    limits: DownloadLimits,
 }
 
 impl Handle {
@@ -117,15 +147,108 @@ impl Handle {
         url: String,
         actual_hash: Hash,
     ) -> Result<Self, Error> {
+        Ok(Self {
+            actual_hash: Some(actual_hash),
+            ..Self::without_hash(cache_path, file_name, hash_type, url)
+        })
+    }
+
+    /// Construct a cache handle that keeps any file it downloads, whatever its hash
+    fn without_hash(
+        cache_path: impl AsRef<Path>,
+        file_name: impl Into<PathBuf>,
+        hash_type: HashType,
+        url: String,
+    ) -> Self {
         let file_name = file_name.into();
         let cache_path = cache_path.as_ref().join(&file_name);
-        Ok(Self {
+        Self {
             url,
             file_name,
             cache_path,
             hash_type,
-            actual_hash,
-        })
+            actual_hash: None,
+            retry: RetryPolicy::default(),
+            mirrors: vec![],
+            segments: SegmentPolicy::default(),
+            github_token: None,
+            github_api_url: crate::checkver::GITHUB_API_URL.to_string(),
+            cookie: None,
+            limits: DownloadLimits::default(),
+        }
+    }
+
+    #[must_use]
+    /// Apply the download settings from the Scoop config
+    pub fn with_config(self, config: &config::Scoop) -> Self {
+        self.with_retry(RetryPolicy::from(config))
+            .with_mirrors(config.download_mirrors.clone())
+            .with_segments(SegmentPolicy::from(config))
+            .with_github_token(requests::github_token(Some(config)))
+            .with_limits(DownloadLimits::shared(config))
+    }
+
+    #[must_use]
+    /// Set whether downloaded and cached files are checked against the manifest hash
+    ///
+    /// If disabled, the handle is treated as if it was opened without a hash,
+    /// so any file that is downloaded or already cached is kept
+    pub fn with_hash_check(mut self, check: bool) -> Self {
+        if !check {
+            self.actual_hash = None;
+        }
+        self
+    }
+
+    #[must_use]
+    /// Set the limits on concurrent downloads and bandwidth, which are shared with every download using the same limits
+    pub fn with_limits(mut self, limits: DownloadLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    #[must_use]
+    /// Set the GitHub token to authenticate downloads from GitHub with
+    ///
+    /// This is required to download release assets from private repositories
+    pub fn with_github_token(mut self, token: Option<String>) -> Self {
+        self.github_token = token;
+        self
+    }
+
+    #[must_use]
+    /// Set the `Cookie` header to send with download requests
+    pub fn with_cookie(mut self, cookie: Option<String>) -> Self {
+        self.cookie = cookie;
+        self
+    }
+
+    #[must_use]
+    /// Use the given base url for the GitHub API, instead of [`crate::checkver::GITHUB_API_URL`]
+    pub fn with_github_api_url(mut self, url: impl Into<String>) -> Self {
+        self.github_api_url = url.into();
+        self
+    }
+
+    #[must_use]
+    /// Set how failed downloads are retried
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    #[must_use]
+    /// Set how downloads are split into segments that are downloaded in parallel
+    pub fn with_segments(mut self, segments: SegmentPolicy) -> Self {
+        self.segments = segments;
+        self
+    }
+
+    #[must_use]
+    /// Set the mirrors to fall back to, in order, if downloading from the original url fails
+    pub fn with_mirrors(mut self, mirrors: Vec<Mirror>) -> Self {
+        self.mirrors = mirrors;
+        self
     }
 
     /// Open a manifest and return a cache handle
@@ -133,45 +256,155 @@ impl Handle {
     /// # Errors
     /// - IO errors
     /// - Missing download URL
+    /// - The manifest does not have a hash for each download URL
     pub fn open_manifest(
         cache_path: impl AsRef<Path>,
         manifest: &Manifest,
         arch: Architecture,
     ) -> Result<Vec<Self>, Error> {
-        let name = &manifest.name;
-        let version = &manifest.version;
-
         let download_urls = manifest
             .download_urls(arch)
-            .ok_or(Error::MissingDownloadUrl)?
-            .into_iter();
+            .ok_or(Error::MissingDownloadUrl)?;
 
         let hashes = manifest
             .install_config(arch)
             .hash
             .map(TOrArrayOfTs::to_vec)
-            // .map(|hash| hash.map(Hash::hash_type).to_vec())
-            .unwrap_or_default()
-            .into_iter();
+            .unwrap_or_default();
+
+        if download_urls.len() != hashes.len() {
+            return Err(Error::HashCountMismatch {
+                urls: download_urls.len(),
+                hashes: hashes.len(),
+            });
+        }
 
         download_urls
+            .into_iter()
             .zip(hashes)
             .map(|(url, hash)| {
-                let file_name = PathBuf::from(&url);
-
-                let file_name = format!("{}#{}#{}", name, version, file_name.display());
-
                 Self::new(
                     cache_path.as_ref(),
-                    PathBuf::from(file_name),
+                    Self::manifest_file_name(manifest, &url),
                     hash.hash_type(),
                     url.url,
                     hash,
                 )
+                .map(|handle| handle.with_cookie(manifest.cookie_header()))
             })
             .collect()
     }
 
+    /// Open a manifest's download urls, without using its hashes
+    ///
+    /// This is used to compute the hashes of a manifest whose urls have been updated,
+    /// so any file that is downloaded is kept in the cache, whatever its hash.
+    /// Only the types of the manifest's hashes are used, defaulting to SHA256 for urls without a hash.
+    ///
+    /// # Errors
+    /// - Missing download URL
+    pub fn open_manifest_urls(
+        cache_path: impl AsRef<Path>,
+        manifest: &Manifest,
+        arch: Architecture,
+    ) -> Result<Vec<Self>, Error> {
+        let download_urls = manifest
+            .download_urls(arch)
+            .ok_or(Error::MissingDownloadUrl)?;
+
+        let hash_types = manifest
+            .install_config(arch)
+            .hash
+            .map(TOrArrayOfTs::to_vec)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|hash| hash.hash_type())
+            .chain(std::iter::repeat(HashType::default()));
+
+        Ok(download_urls
+            .into_iter()
+            .zip(hash_types)
+            .map(|(url, hash_type)| {
+                Self::without_hash(
+                    cache_path.as_ref(),
+                    Self::manifest_file_name(manifest, &url),
+                    hash_type,
+                    url.url,
+                )
+                .with_cookie(manifest.cookie_header())
+            })
+            .collect())
+    }
+
+    /// Get the cache file name for a manifest's download url
+    fn manifest_file_name(manifest: &Manifest, url: &DownloadUrl) -> PathBuf {
+        let file_name = PathBuf::from(url);
+
+        PathBuf::from(format!(
+            "{}#{}#{}",
+            manifest.name,
+            manifest.version,
+            file_name.display()
+        ))
+    }
+
+    #[must_use]
+    /// Get the hash stored in the manifest
+    ///
+    /// Returns [`None`] if the handle was opened without a hash
+    pub fn actual_hash(&self) -> Option<&Hash> {
+        self.actual_hash.as_ref()
+    }
+
+    /// Check if the hash matches the hash stored in the manifest
+    ///
+    /// Any hash matches if the handle was opened without a hash
+    fn hash_matches(&self, hash: &Hash) -> bool {
+        self.actual_hash
+            .as_ref()
+            .map_or(true, |actual_hash| hash.matches(actual_hash))
+    }
+
+    /// Compute the hash of the cached file
+    ///
+    /// Returns [`None`] if the file is not in the cache
+    ///
+    /// # Errors
+    /// - If the cached file cannot be read
+    pub async fn cached_hash(&self) -> Result<Option<Hash>, Error> {
+        if !self.cache_path.exists() {
+            return Ok(None);
+        }
+
+        Ok(Some(file_hash(&self.cache_path, self.hash_type).await?))
+    }
+
+    /// Get the headers to send with requests to the url
+    fn headers(&self, url: &str) -> header::HeaderMap {
+        let mut headers = requests::auth_headers_with_api(
+            url,
+            self.github_token.as_deref(),
+            Some(&self.github_api_url),
+        );
+
+        if let Some(cookie) = self
+            .cookie
+            .as_deref()
+            .and_then(|cookie| header::HeaderValue::from_str(cookie).ok())
+        {
+            headers.insert(header::COOKIE, cookie);
+        }
+
+        headers
+    }
+
+    /// Get the path that the file is downloaded to, before it is verified
+    fn part_path(&self) -> PathBuf {
+        let mut part_path = self.cache_path.clone().into_os_string();
+        part_path.push(".part");
+        part_path.into()
+    }
+
     /// Create a new downloader
     ///
     /// # Errors
@@ -189,13 +422,22 @@ impl Handle {
 /// A cache handle downloader
 pub struct Downloader {
     cache: Handle,
-    resp: Response,
+    client: reqwest::Client,
+    /// The response to download from, or `None` if the file is already cached
+    resp: Option<Response>,
+    /// The number of bytes already downloaded to the partial file
+    offset: u64,
+    /// Allows the download to continue while the number of concurrent downloads is limited
+    _permit: Option<OwnedSemaphorePermit>,
     pb: Option<ProgressBar>,
 }
 
 impl Downloader {
     /// Create a new downloader
     ///
+    /// If a partial download was left behind by a previous attempt,
+    /// it will be resumed if the server supports range requests
+    ///
     /// # Errors
     /// - If the request fails
     ///
@@ -206,15 +448,27 @@ impl Downloader {
         cache: Handle,
         mp: Option<&MultiProgress>,
     ) -> Result<Self, Error> {
-        let resp = T::new().client().get(&cache.url).send().await?;
+        let client = T::new().client().clone();
 
-        if !resp.status().is_success() {
-            return Err(Error::ErrorCode(resp.status()));
-        }
+        let mut permit = None;
 
-        debug!("Status Code: {}", resp.status());
+        let (resp, offset, content_length) = if cache.cache_path.exists() {
+            let content_length = tokio::fs::metadata(&cache.cache_path).await?.len();
 
-        let content_length = resp.content_length().unwrap_or_default();
+            (None, 0, content_length)
+        } else if offline::enabled() {
+            return Err(Error::Offline);
+        } else {
+            permit = cache.limits.acquire().await;
+
+            let (resp, offset) = Self::request(&client, &cache).await?;
+
+            debug!("Status Code: {}", resp.status());
+
+            let content_length = offset + resp.content_length().unwrap_or_default();
+
+            (Some(resp), offset, content_length)
+        };
 
         let pb = mp.map(|mp| {
             let message = {
@@ -240,104 +494,900 @@ impl Downloader {
                     .with_finish(indicatif::ProgressFinish::WithMessage("Finished ✅".into())),
             );
 
+            pb.set_position(offset);
+
             pb
         });
 
-        Ok(Self { cache, resp, pb })
+        Ok(Self {
+            cache,
+            client,
+            resp,
+            offset,
+            _permit: permit,
+            pb,
+        })
+    }
+
+    /// Request the file, falling back to each mirror in turn if the request fails
+    ///
+    /// GitHub release assets that are not found are requested through the GitHub API,
+    /// in case they belong to a private repository
+    ///
+    /// Returns the response, and the offset in the file that it starts at
+    async fn request(client: &reqwest::Client, cache: &Handle) -> Result<(Response, u64), Error> {
+        if offline::enabled() {
+            return Err(Error::Offline);
+        }
+
+        let mut result = Self::request_with_retries(client, cache, &cache.url).await;
+
+        if let (Err(Error::ErrorCode(StatusCode::NOT_FOUND)), Some(token)) =
+            (&result, &cache.github_token)
+        {
+            // A failed API lookup is treated like any other failed attempt, so the mirrors are still tried
+            match github::asset_api_url(client, &cache.github_api_url, &cache.url, token).await {
+                Ok(Some(url)) => {
+                    debug!("Downloading {} from the GitHub API", cache.url);
+
+                    result = Self::request_with_retries(client, cache, &url).await;
+                }
+                Ok(None) => {}
+                Err(error) => warn!(
+                    "Failed to find {} through the GitHub API: {error}",
+                    cache.url
+                ),
+            }
+        }
+
+        for url in cache
+            .mirrors
+            .iter()
+            .filter_map(|mirror| mirror.rewrite(&cache.url))
+        {
+            let Err(error) = &result else {
+                break;
+            };
+
+            warn!("Failed to download {}: {error}. Trying {url}", cache.url);
+
+            result = Self::request_with_retries(client, cache, &url).await;
+        }
+
+        result
+    }
+
+    /// Request the file from the url, retrying transient failures
+    async fn request_with_retries(
+        client: &reqwest::Client,
+        cache: &Handle,
+        url: &str,
+    ) -> Result<(Response, u64), Error> {
+        let mut attempt = 0;
+
+        loop {
+            let retry_after = match Self::send(client, cache, url).await {
+                Ok((resp, offset)) if resp.status().is_success() => return Ok((resp, offset)),
+                Ok((resp, _)) => {
+                    if !retry::is_transient_status(resp.status()) || attempt >= cache.retry.retries
+                    {
+                        return Err(Error::ErrorCode(resp.status()));
+                    }
+
+                    retry::retry_after(resp.headers())
+                }
+                Err(error) => {
+                    if !retry::is_transient_error(&error) || attempt >= cache.retry.retries {
+                        return Err(error.into());
+                    }
+
+                    None
+                }
+            };
+
+            let delay = cache.retry.delay(attempt, retry_after);
+            debug!("Retrying {url} in {delay:?}");
+            tokio::time::sleep(delay).await;
+
+            attempt += 1;
+        }
+    }
+
+    /// Send a request for the file, resuming from the partial file if possible
+    ///
+    /// Returns the response, and the offset in the file that it starts at
+    async fn send(
+        client: &reqwest::Client,
+        cache: &Handle,
+        url: &str,
+    ) -> reqwest::Result<(Response, u64)> {
+        let offset = tokio::fs::metadata(cache.part_path())
+            .await
+            .map(|metadata| metadata.len())
+            .unwrap_or_default();
+
+        let request = || {
+            let request = client.get(url).headers(cache.headers(url));
+
+            if github::is_asset_api_url(url) {
+                request.header(header::ACCEPT, "application/octet-stream")
+            } else {
+                request
+            }
+        };
+
+        if offset > 0 {
+            let resp = request()
+                .header(header::RANGE, format!("bytes={offset}-"))
+                .send()
+                .await?;
+
+            match resp.status() {
+                StatusCode::PARTIAL_CONTENT if content_range_start(&resp) == Some(offset) => {
+                    debug!("Resuming download from byte {offset}");
+                    return Ok((resp, offset));
+                }
+                // The server ignored the range, and sent the whole file
+                StatusCode::OK => return Ok((resp, 0)),
+                status => debug!("Could not resume download ({status}), starting over"),
+            }
+        }
+
+        let resp = request().send().await?;
+
+        Ok((resp, 0))
     }
 
     /// Download the file to the cache
     ///
+    /// The file is downloaded to a partial file first,
+    /// and is only moved into the cache once its hash matches the manifest.
+    /// If the download is interrupted, the partial file is kept so it can be resumed.
+    ///
+    /// If the file is already cached, but does not match the manifest hash,
+    /// it is removed from the cache and downloaded again, unless offline mode is enabled.
+    ///
     /// Returns the cache file name, and the computed hash
     ///
     /// # Errors
     /// - If the file cannot be written to the cache
-    pub async fn download(self) -> Result<DownloadResult, Error> {
-        let actual_hash = self.cache.actual_hash.clone();
-
+    /// - If the connection is lost during the download
+    /// - If the cached file does not match the manifest hash while offline
+    pub async fn download(mut self) -> Result<DownloadResult, Error> {
         let file_name = self.cache.file_name.clone();
-        let hash_bytes = match self.cache.hash_type {
-            HashType::SHA512 => self.handle_buf::<sha2::Sha512>().await,
-            HashType::SHA256 => self.handle_buf::<sha2::Sha256>().await,
-            HashType::SHA1 => self.handle_buf::<sha1::Sha1>().await,
-            HashType::MD5 => self.handle_buf::<md5::Md5>().await,
-        }?;
+
+        let cached = self.resp.is_none();
+        let mut computed_hash = self.compute_hash().await?;
+
+        if cached && !self.cache.hash_matches(&computed_hash) {
+            // The cached file cannot be downloaded again, so it is kept for the caller to deal with
+            if offline::enabled() {
+                return Err(Error::Offline);
+            }
+
+            warn!(
+                "Cached file {} does not match the manifest hash. Downloading it again",
+                file_name.display()
+            );
+
+            tokio::fs::remove_file(&self.cache.cache_path).await?;
+
+            self._permit = self.cache.limits.acquire().await;
+            let (resp, offset) = Self::request(&self.client, &self.cache).await?;
+            self.set_response(resp, offset);
+
+            computed_hash = self.compute_hash().await?;
+        }
+
+        let actual_hash = self
+            .cache
+            .actual_hash
+            .clone()
+            .unwrap_or_else(|| computed_hash.clone());
 
         Ok(DownloadResult {
             file_name: file_name.as_path().try_into()?,
-            computed_hash: Hash::from_hex(&hash_bytes),
+            computed_hash,
             actual_hash,
         })
     }
 
-    async fn handle_buf<D: Digest>(self) -> Result<Vec<u8>, Error> {
-        use tokio::fs::File;
+    async fn compute_hash(&mut self) -> Result<Hash, Error> {
+        let mut attempt = 0;
 
-        enum Source<T: futures::Stream<Item = reqwest::Result<bytes::Bytes>> + std::marker::Unpin> {
-            Cache(futures::prelude::stream::IntoStream<FramedRead<File, BytesCodec>>),
-            Network(T),
-        }
+        loop {
+            let downloading = self.resp.is_some();
 
-        impl<T> Stream for Source<T>
-        where
-            T: futures::Stream<Item = reqwest::Result<bytes::Bytes>> + std::marker::Unpin,
-        {
-            type Item = reqwest::Result<bytes::Bytes>;
-
-            fn poll_next(
-                self: std::pin::Pin<&mut Self>,
-                cx: &mut std::task::Context<'_>,
-            ) -> std::task::Poll<Option<Self::Item>> {
-                match self.get_mut() {
-                    Source::Cache(file) => file.poll_next_unpin(cx).map(|bytes| match bytes {
-                        Some(Ok(bytes)) => Some(Ok(BytesMut::freeze(bytes))),
-                        _ => None,
-                    }),
-                    Source::Network(resp) => resp.poll_next_unpin(cx),
+            let result = match self.cache.hash_type {
+                HashType::SHA512 => self.handle_buf::<sha2::Sha512>().await,
+                HashType::SHA256 => self.handle_buf::<sha2::Sha256>().await,
+                HashType::SHA1 => self.handle_buf::<sha1::Sha1>().await,
+                HashType::MD5 => self.handle_buf::<md5::Md5>().await,
+            };
+
+            match result {
+                // The partial file is kept, so the download picks up where it left off
+                Err(Error::Reqwest(error))
+                    if downloading
+                        && retry::is_transient_error(&error)
+                        && attempt < self.cache.retry.retries =>
+                {
+                    let delay = self.cache.retry.delay(attempt, None);
+                    warn!(
+                        "Download of {} was interrupted: {error}. Resuming in {delay:?}",
+                        self.cache.url
+                    );
+                    tokio::time::sleep(delay).await;
+
+                    attempt += 1;
+
+                    let (resp, offset) = Self::request(&self.client, &self.cache).await?;
+                    self.set_response(resp, offset);
                 }
+                result => return Ok(Hash::from_hex(&result?)),
             }
         }
+    }
+
+    fn set_response(&mut self, resp: Response, offset: u64) {
+        if let Some(pb) = &self.pb {
+            pb.set_length(offset + resp.content_length().unwrap_or_default());
+            pb.set_position(offset);
+        }
+
+        self.resp = Some(resp);
+        self.offset = offset;
+    }
 
-        let cache_path = self.cache.cache_path.clone();
+    async fn handle_buf<D: Digest>(&mut self) -> Result<Vec<u8>, Error> {
+        let mut hasher = D::new();
 
-        let mut reader = if cache_path.exists() {
+        let Some(resp) = self.resp.take() else {
             debug!("Loading from cache");
             if let Some(pb) = &self.pb {
                 pb.set_prefix("📦");
             }
-            let file = File::open(&cache_path).await?;
-            let stream = FramedRead::with_capacity(file, BytesCodec::new(), {
-                // 1 MiB buffer
-                1024 * 1024
-            });
 
-            Source::Cache(stream.into_stream())
-        } else {
-            debug!("Downloading via network");
-            Source::Network(self.resp.bytes_stream())
-        };
+            hash_file(&mut hasher, &self.cache.cache_path, self.pb.as_ref()).await?;
 
-        let mut cache_file = match &reader {
-            Source::Cache(_) => None,
-            Source::Network(_) => Some(File::create(&cache_path).await?),
+            return Ok(hasher.finalize()[..].to_vec());
         };
 
-        let mut hasher = D::new();
+        debug!("Downloading via network");
 
-        while let Some(Ok(chunk)) = reader.next().await {
-            hasher.update(&chunk);
+        let part_path = self.cache.part_path();
+
+        if self.offset == 0 {
+            if let Some(ranges) = self.cache.segments.segments(&resp) {
+                debug!("Downloading in {} segments", ranges.len());
 
-            if let Some(cache_file) = cache_file.as_mut() {
-                cache_file.write_all(&chunk).await?;
+                let headers = self.cache.headers(resp.url().as_str());
+                let result = segments::download(
+                    &self.client,
+                    resp,
+                    ranges,
+                    headers,
+                    &self.cache.limits,
+                    &part_path,
+                    self.pb.as_ref(),
+                )
+                .await;
+
+                if let Err(error) = result {
+                    // Segments are written out of order, so the partial file can't be resumed
+                    _ = tokio::fs::remove_file(&part_path).await;
+                    return Err(error);
+                }
+
+                hash_file(&mut hasher, &part_path, None).await?;
+
+                return self.finish(hasher.finalize()[..].to_vec()).await;
             }
+        }
+
+        let mut part_file = if self.offset > 0 {
+            // The progress bar already includes the partial file
+            hash_file(&mut hasher, &part_path, None).await?;
+
+            OpenOptions::new().append(true).open(&part_path).await?
+        } else {
+            File::create(&part_path).await?
+        };
 
-            let chunk_length = chunk.len();
+        let mut stream = resp.bytes_stream();
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(err) => {
+                    // Keep what was downloaded, so it can be resumed later
+                    part_file.flush().await?;
+                    return Err(err.into());
+                }
+            };
+
+            self.cache.limits.throttle(chunk.len() as u64).await;
+
+            hasher.update(&chunk);
+            part_file.write_all(&chunk).await?;
 
             if let Some(pb) = &self.pb {
-                pb.inc(chunk_length as u64);
+                pb.inc(chunk.len() as u64);
             }
         }
 
+        part_file.flush().await?;
+        drop(part_file);
+
+        self.finish(hasher.finalize()[..].to_vec()).await
+    }
+
+    /// Move the downloaded file into the cache if its hash matches the manifest
+    async fn finish(&self, hash_bytes: Vec<u8>) -> Result<Vec<u8>, Error> {
+        let part_path = self.cache.part_path();
+
+        if self.cache.hash_matches(&Hash::from_hex(&hash_bytes)) {
+            tokio::fs::rename(&part_path, &self.cache.cache_path).await?;
+        } else {
+            // Resuming a corrupted file would never produce the right hash
+            tokio::fs::remove_file(&part_path).await?;
+        }
+
+        Ok(hash_bytes)
+    }
+}
+
+async fn file_hash(path: &Path, hash_type: HashType) -> Result<Hash, Error> {
+    async fn compute<D: Digest>(path: &Path) -> Result<Vec<u8>, Error> {
+        let mut hasher = D::new();
+        hash_file(&mut hasher, path, None).await?;
+
         Ok(hasher.finalize()[..].to_vec())
     }
+
+    let hash_bytes = match hash_type {
+        HashType::SHA512 => compute::<sha2::Sha512>(path).await,
+        HashType::SHA256 => compute::<sha2::Sha256>(path).await,
+        HashType::SHA1 => compute::<sha1::Sha1>(path).await,
+        HashType::MD5 => compute::<md5::Md5>(path).await,
+    }?;
+
+    Ok(Hash::from_hex(&hash_bytes))
+}
+
+async fn hash_file<D: Digest>(
+    hasher: &mut D,
+    path: &Path,
+    pb: Option<&ProgressBar>,
+) -> Result<(), Error> {
+    let file = File::open(path).await?;
+    let mut stream = FramedRead::with_capacity(file, BytesCodec::new(), {
+        // 1 MiB buffer
+        1024 * 1024
+    });
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+
+        hasher.update(&chunk);
+
+        if let Some(pb) = pb {
+            pb.inc(chunk.len() as u64);
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the first byte of a partial response from its `Content-Range` header
+fn content_range_start(resp: &Response) -> Option<u64> {
+    resp.headers()
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes ")?
+        .split_once('-')?
+        .0
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use crate::{
+        packages::CreateManifest,
+        test_server::{Response, TestServer},
+    };
+
+    use super::*;
+
+    const BODY: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    const DROPPED_AT: usize = 10;
+
+    #[derive(Default)]
+    struct TestClient(reqwest::Client);
+
+    impl ClientLike<reqwest::Client> for TestClient {
+        fn client(&self) -> &reqwest::Client {
+            &self.0
+        }
+    }
+
+    fn handle(cache_dir: &Path, server: &TestServer) -> Handle {
+        Handle::new(
+            cache_dir,
+            "app#1.0.0#app.zip",
+            HashType::SHA256,
+            server.url("/app.zip"),
+            Hash::from_hex(&sha2::Sha256::digest(BODY)),
+        )
+        .unwrap()
+        .with_retry(RetryPolicy::none())
+    }
+
+    fn fast_retry() -> RetryPolicy {
+        RetryPolicy {
+            retries: 3,
+            delay: Duration::from_millis(1),
+        }
+    }
+
+    /// A server that hangs up part way through the body, unless the rest of the body is requested
+    fn dropping_server() -> TestServer {
+        TestServer::start(|req| match req.header("range") {
+            Some(range) => {
+                assert_eq!(range, format!("bytes={DROPPED_AT}-"));
+
+                Response::status(206)
+                    .with_header(
+                        "Content-Range",
+                        format!("bytes {DROPPED_AT}-{}/{}", BODY.len() - 1, BODY.len()),
+                    )
+                    .with_body(&BODY[DROPPED_AT..])
+            }
+            // Advertise the full length, but hang up part way through
+            None => Response::ok(&BODY[..DROPPED_AT])
+                .with_header("Content-Length", BODY.len().to_string()),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_resumes_interrupted_download() {
+        let server = dropping_server();
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = dir.path();
+        let new_handle = || handle(cache_dir, &server);
+
+        let interrupted = Downloader::new::<TestClient>(new_handle(), None)
+            .await
+            .unwrap()
+            .download()
+            .await;
+        assert!(interrupted.is_err());
+
+        let cache_path = cache_dir.join("app#1.0.0#app.zip");
+        let part_path = new_handle().part_path();
+        assert!(!cache_path.exists());
+        assert_eq!(
+            std::fs::metadata(&part_path).unwrap().len(),
+            DROPPED_AT as u64
+        );
+
+        let result = Downloader::new::<TestClient>(new_handle(), None)
+            .await
+            .unwrap()
+            .download()
+            .await
+            .unwrap();
+
+        assert!(result.computed_hash.matches(&result.actual_hash));
+        assert_eq!(std::fs::read(&cache_path).unwrap(), BODY);
+        assert!(!part_path.exists());
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    fn manifest(server: &TestServer, hashes: &str) -> Manifest {
+        let mut manifest = Manifest::from_str(format!(
+            r#"{{"version":"1.0.0","url":["{}","{}"],"hash":{hashes}}}"#,
+            server.url("/app.zip"),
+            server.url("/other.zip"),
+        ))
+        .unwrap();
+        manifest.name = "app".to_string();
+
+        manifest
+    }
+
+    #[test]
+    fn test_open_manifest_hash_count_mismatch() {
+        let server = TestServer::start(|_| Response::ok(BODY));
+        let dir = tempfile::tempdir().unwrap();
+
+        let manifest = manifest(&server, &format!(r#""{}""#, "0".repeat(64)));
+
+        assert!(matches!(
+            Handle::open_manifest(dir.path(), &manifest, Architecture::ARCH),
+            Err(Error::HashCountMismatch { urls: 2, hashes: 1 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_manifest_urls_ignore_hashes() {
+        let server = TestServer::start(|_| Response::ok(BODY));
+        let dir = tempfile::tempdir().unwrap();
+
+        // A stale hash for the first url only, as if the urls had just been updated
+        let manifest = manifest(&server, &format!(r#"["{}"]"#, "0".repeat(40)));
+
+        let handles =
+            Handle::open_manifest_urls(dir.path(), &manifest, Architecture::ARCH).unwrap();
+        assert_eq!(handles.len(), 2);
+
+        for (handle, hash_type) in handles.into_iter().zip([HashType::SHA1, HashType::SHA256]) {
+            assert!(handle.actual_hash().is_none());
+            let cache_path = dir.path().join(&handle.file_name);
+
+            let result = Downloader::new::<TestClient>(handle, None)
+                .await
+                .unwrap()
+                .download()
+                .await
+                .unwrap();
+
+            assert_eq!(result.computed_hash.hash_type(), hash_type);
+            assert!(result.computed_hash.matches(&result.actual_hash));
+            assert_eq!(std::fs::read(cache_path).unwrap(), BODY);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_evicts_corrupted_cache_file() {
+        let server = TestServer::start(|_| Response::ok(BODY));
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = dir.path();
+        let handle = handle(cache_dir, &server);
+
+        let cache_path = cache_dir.join(&handle.file_name);
+        std::fs::write(&cache_path, &BODY[..10]).unwrap();
+
+        assert!(!handle
+            .cached_hash()
+            .await
+            .unwrap()
+            .unwrap()
+            .matches(handle.actual_hash().unwrap()));
+
+        let result = Downloader::new::<TestClient>(handle, None)
+            .await
+            .unwrap()
+            .download()
+            .await
+            .unwrap();
+
+        assert!(result.computed_hash.matches(&result.actual_hash));
+        assert_eq!(std::fs::read(&cache_path).unwrap(), BODY);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_without_hash_check_keeps_mismatching_files() {
+        const MODIFIED: &[u8] = b"modified";
+
+        let server = TestServer::start(|_| Response::ok(MODIFIED));
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = dir.path();
+        let cache_path = cache_dir.join("app#1.0.0#app.zip");
+
+        // Downloaded files are kept, even though they do not match the manifest hash
+        let result =
+            Downloader::new::<TestClient>(handle(cache_dir, &server).with_hash_check(false), None)
+                .await
+                .unwrap()
+                .download()
+                .await
+                .unwrap();
+
+        assert!(result.computed_hash.matches(&result.actual_hash));
+        assert_eq!(std::fs::read(&cache_path).unwrap(), MODIFIED);
+
+        // Cached files are not evicted
+        let result =
+            Downloader::new::<TestClient>(handle(cache_dir, &server).with_hash_check(false), None)
+                .await
+                .unwrap()
+                .download()
+                .await
+                .unwrap();
+
+        assert!(result.computed_hash.matches(&result.actual_hash));
+        assert_eq!(std::fs::read(&cache_path).unwrap(), MODIFIED);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retries_interrupted_download() {
+        let server = dropping_server();
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = dir.path();
+        let handle = handle(cache_dir, &server).with_retry(fast_retry());
+
+        let result = Downloader::new::<TestClient>(handle, None)
+            .await
+            .unwrap()
+            .download()
+            .await
+            .unwrap();
+
+        assert!(result.computed_hash.matches(&result.actual_hash));
+        assert_eq!(
+            std::fs::read(cache_dir.join("app#1.0.0#app.zip")).unwrap(),
+            BODY
+        );
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retries_server_errors() {
+        let server = TestServer::start({
+            let failures = AtomicUsize::new(2);
+
+            move |_| {
+                if failures
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                    .is_ok()
+                {
+                    Response::status(503).with_header("Retry-After", "0")
+                } else {
+                    Response::ok(BODY)
+                }
+            }
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = dir.path();
+        let handle = handle(cache_dir, &server).with_retry(fast_retry());
+
+        let result = Downloader::new::<TestClient>(handle, None)
+            .await
+            .unwrap()
+            .download()
+            .await
+            .unwrap();
+
+        assert!(result.computed_hash.matches(&result.actual_hash));
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_mirror() {
+        let server = TestServer::start(|req| {
+            if req.path.starts_with("/mirror/") {
+                Response::ok(BODY)
+            } else {
+                Response::status(404)
+            }
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = dir.path();
+        let handle = handle(cache_dir, &server).with_mirrors(vec![Mirror {
+            pattern: r"/(app\.zip)$".into(),
+            replacement: "/mirror/$1".into(),
+        }]);
+
+        let result = Downloader::new::<TestClient>(handle, None)
+            .await
+            .unwrap()
+            .download()
+            .await
+            .unwrap();
+
+        assert!(result.computed_hash.matches(&result.actual_hash));
+        assert_eq!(
+            server
+                .requests()
+                .iter()
+                .map(|req| req.path.as_str())
+                .collect::<Vec<_>>(),
+            ["/app.zip", "/mirror/app.zip"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sends_manifest_cookie() {
+        const COOKIE: &str = "oraclelicense=accept-securebackup-cookie";
+
+        let server = TestServer::start(|req| {
+            if req.header("cookie") == Some(COOKIE) {
+                Response::ok(BODY)
+            } else {
+                Response::status(403)
+            }
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = dir.path();
+
+        let rejected = Downloader::new::<TestClient>(handle(cache_dir, &server), None).await;
+        assert!(matches!(
+            rejected,
+            Err(Error::ErrorCode(StatusCode::FORBIDDEN))
+        ));
+
+        let handle = handle(cache_dir, &server).with_cookie(Some(COOKIE.into()));
+        let result = Downloader::new::<TestClient>(handle, None)
+            .await
+            .unwrap()
+            .download()
+            .await
+            .unwrap();
+
+        assert!(result.computed_hash.matches(&result.actual_hash));
+    }
+
+    #[tokio::test]
+    async fn test_downloads_private_release_asset() {
+        let server = TestServer::start(|req| match req.path.as_str() {
+            "/repos/winpax/private/releases/tags/v1.0.0" => {
+                if req.header("authorization") != Some("Bearer ghp_token") {
+                    return Response::status(404);
+                }
+
+                Response::ok(format!(
+                    r#"{{"assets":[{{"name":"app.zip","url":"http://{}/repos/winpax/private/releases/assets/1"}}]}}"#,
+                    req.header("host").unwrap()
+                ))
+            }
+            "/repos/winpax/private/releases/assets/1"
+                if req.header("accept") == Some("application/octet-stream") =>
+            {
+                Response::ok(BODY)
+            }
+            _ => Response::status(404),
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = dir.path();
+        let handle = Handle::new(
+            cache_dir,
+            "app#1.0.0#app.zip",
+            HashType::SHA256,
+            server.url("/winpax/private/releases/download/v1.0.0/app.zip"),
+            Hash::from_hex(&sha2::Sha256::digest(BODY)),
+        )
+        .unwrap()
+        .with_retry(RetryPolicy::none())
+        .with_github_token(Some("ghp_token".into()))
+        .with_github_api_url(server.url(""));
+
+        let result = Downloader::new::<TestClient>(handle, None)
+            .await
+            .unwrap()
+            .download()
+            .await
+            .unwrap();
+
+        assert!(result.computed_hash.matches(&result.actual_hash));
+        assert_eq!(
+            server
+                .requests()
+                .iter()
+                .map(|req| req.path.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "/winpax/private/releases/download/v1.0.0/app.zip",
+                "/repos/winpax/private/releases/tags/v1.0.0",
+                "/repos/winpax/private/releases/assets/1"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_github_api_failure_falls_back_to_mirror() {
+        let server = TestServer::start(|req| match req.path.as_str() {
+            "/mirror/app.zip" => Response::ok(BODY),
+            "/repos/winpax/private/releases/tags/v1.0.0" => Response::status(403),
+            _ => Response::status(404),
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let handle = Handle::new(
+            dir.path(),
+            "app#1.0.0#app.zip",
+            HashType::SHA256,
+            server.url("/winpax/private/releases/download/v1.0.0/app.zip"),
+            Hash::from_hex(&sha2::Sha256::digest(BODY)),
+        )
+        .unwrap()
+        .with_retry(RetryPolicy::none())
+        .with_github_token(Some("ghp_token".into()))
+        .with_github_api_url(server.url(""))
+        .with_mirrors(vec![Mirror {
+            pattern: r"/winpax/private/releases/download/v1\.0\.0/(app\.zip)$".into(),
+            replacement: "/mirror/$1".into(),
+        }]);
+
+        let result = Downloader::new::<TestClient>(handle, None)
+            .await
+            .unwrap()
+            .download()
+            .await
+            .unwrap();
+
+        assert!(result.computed_hash.matches(&result.actual_hash));
+        assert_eq!(
+            server
+                .requests()
+                .iter()
+                .map(|req| req.path.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "/winpax/private/releases/download/v1.0.0/app.zip",
+                "/repos/winpax/private/releases/tags/v1.0.0",
+                "/mirror/app.zip"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_segmented_download() {
+        let server = TestServer::start(|req| {
+            let Some(range) = req.header("range") else {
+                return Response::ok(BODY).with_header("Accept-Ranges", "bytes");
+            };
+
+            let (start, end) = range
+                .strip_prefix("bytes=")
+                .and_then(|range| range.split_once('-'))
+                .unwrap();
+            let (start, end) = (
+                start.parse::<usize>().unwrap(),
+                end.parse::<usize>().unwrap(),
+            );
+
+            Response::status(206)
+                .with_header(
+                    "Content-Range",
+                    format!("bytes {start}-{end}/{}", BODY.len()),
+                )
+                .with_body(&BODY[start..=end])
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = dir.path();
+        let handle = handle(cache_dir, &server).with_segments(SegmentPolicy {
+            connections: 4,
+            min_segment_size: 8,
+        });
+
+        let result = Downloader::new::<TestClient>(handle, None)
+            .await
+            .unwrap()
+            .download()
+            .await
+            .unwrap();
+
+        assert!(result.computed_hash.matches(&result.actual_hash));
+        assert_eq!(
+            std::fs::read(cache_dir.join("app#1.0.0#app.zip")).unwrap(),
+            BODY
+        );
+
+        let mut ranges = server
+            .requests()
+            .iter()
+            .filter_map(|req| req.header("range").map(String::from))
+            .collect::<Vec<_>>();
+        ranges.sort();
+        assert_eq!(ranges, ["bytes=18-26", "bytes=27-35", "bytes=9-17"]);
+    }
 }
@@ -0,0 +1,171 @@
+//! Downloading release assets from private GitHub repositories
+//!
+//! Release download urls on `github.com` cannot be authenticated for private repositories,
+//! so their assets are downloaded through the GitHub API instead.
+
+use reqwest::{header, StatusCode};
+use serde::Deserialize;
+
+use super::Error;
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Asset {
+    name: String,
+    url: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+/// A release asset, parsed from its download url
+struct ReleaseAsset {
+    owner: String,
+    repo: String,
+    tag: String,
+    name: String,
+}
+
+impl ReleaseAsset {
+    /// Parse a `https://github.com/{owner}/{repo}/releases/download/{tag}/{name}` url
+    ///
+    /// Urls on the same host as the API are accepted too, as is the case for GitHub Enterprise
+    fn parse(url: &str, api_url: &str) -> Option<Self> {
+        let url = url::Url::parse(url).ok()?;
+        let api_host = url::Url::parse(api_url)
+            .ok()
+            .and_then(|api_url| api_url.host_str().map(ToString::to_string));
+
+        if url.host_str() != Some("github.com") && url.host_str() != api_host.as_deref() {
+            return None;
+        }
+
+        let mut segments = url.path_segments()?.collect::<Vec<_>>();
+        if segments.len() < 6 || segments[2] != "releases" || segments[3] != "download" {
+            return None;
+        }
+
+        let name = segments.pop()?;
+        let decode = |segment: &str| urlencoding::decode(segment).ok().map(|s| s.into_owned());
+
+        Some(Self {
+            owner: segments[0].to_string(),
+            repo: segments[1].to_string(),
+            tag: decode(&segments[4..].join("/"))?,
+            name: decode(name)?,
+        })
+    }
+}
+
+#[must_use]
+/// Check if the url is a GitHub API release asset url, which must be requested with `Accept: application/octet-stream`
+pub fn is_asset_api_url(url: &str) -> bool {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|url| {
+            let segments = url.path_segments()?.collect::<Vec<_>>();
+
+            Some(
+                matches!(segments.as_slice(), [.., "repos", _, _, "releases", "assets", id] if !id.is_empty()),
+            )
+        })
+        .unwrap_or_default()
+}
+
+/// Find the GitHub API url to download a release asset from
+///
+/// Returns [`None`] if the url is not a GitHub release download url,
+/// or the release does not have a matching asset
+///
+/// # Errors
+/// - The release could not be fetched
+/// - The GitHub API response is invalid
+pub async fn asset_api_url(
+    client: &reqwest::Client,
+    api_url: &str,
+    url: &str,
+    token: &str,
+) -> Result<Option<String>, Error> {
+    let Some(asset) = ReleaseAsset::parse(url, api_url) else {
+        return Ok(None);
+    };
+
+    let resp = client
+        .get(format!(
+            "{}/repos/{}/{}/releases/tags/{}",
+            api_url.trim_end_matches('/'),
+            asset.owner,
+            asset.repo,
+            urlencoding::encode(&asset.tag)
+        ))
+        .header(header::ACCEPT, "application/vnd.github+json")
+        .bearer_auth(token)
+        .send()
+        .await?;
+
+    match resp.status() {
+        StatusCode::NOT_FOUND => return Ok(None),
+        status if !status.is_success() => return Err(Error::ErrorCode(status)),
+        _ => {}
+    }
+
+    let release: Release = serde_json::from_slice(&resp.bytes().await?)?;
+
+    Ok(release
+        .assets
+        .into_iter()
+        .find(|candidate| candidate.name == asset.name)
+        .map(|asset| asset.url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const API_URL: &str = "https://api.github.com";
+
+    #[test]
+    fn test_parse_release_asset() {
+        assert_eq!(
+            ReleaseAsset::parse(
+                "https://github.com/winpax/sfsu/releases/download/v1.0.0/sfsu%20setup.exe",
+                API_URL
+            ),
+            Some(ReleaseAsset {
+                owner: "winpax".into(),
+                repo: "sfsu".into(),
+                tag: "v1.0.0".into(),
+                name: "sfsu setup.exe".into(),
+            })
+        );
+
+        assert_eq!(
+            ReleaseAsset::parse("https://github.com/winpax/sfsu/archive/v1.0.0.zip", API_URL),
+            None
+        );
+        assert_eq!(
+            ReleaseAsset::parse(
+                "https://example.com/winpax/sfsu/releases/download/v1/a.zip",
+                API_URL
+            ),
+            None
+        );
+        assert!(ReleaseAsset::parse(
+            "https://github.example.com/winpax/sfsu/releases/download/v1/a.zip",
+            "https://github.example.com/api/v3"
+        )
+        .is_some());
+    }
+
+    #[test]
+    fn test_asset_api_url() {
+        assert!(is_asset_api_url(
+            "https://api.github.com/repos/winpax/sfsu/releases/assets/1"
+        ));
+        assert!(!is_asset_api_url(
+            "https://api.github.com/repos/winpax/sfsu/releases/tags/v1.0.0"
+        ));
+    }
+}
@@ -0,0 +1,167 @@
+//! Limiting the number of concurrent downloads, and their combined bandwidth
+
+use std::{
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::config;
+
+#[derive(Debug, Clone, Default)]
+/// Limits shared between downloads
+///
+/// Clones share the same limits, so every download using a clone is throttled together
+pub struct DownloadLimits {
+    downloads: Option<Arc<Semaphore>>,
+    bandwidth: Option<Arc<Bandwidth>>,
+}
+
+impl From<&config::Scoop> for DownloadLimits {
+    fn from(config: &config::Scoop) -> Self {
+        Self::new(
+            config
+                .download_concurrency
+                .and_then(|limit| usize::try_from(limit).ok()),
+            config.download_bandwidth_limit,
+        )
+    }
+}
+
+impl DownloadLimits {
+    #[must_use]
+    /// Construct new download limits
+    ///
+    /// - `max_downloads`: the maximum number of files downloaded at once
+    /// - `bytes_per_second`: the maximum combined download speed
+    pub fn new(max_downloads: Option<usize>, bytes_per_second: Option<u64>) -> Self {
+        Self {
+            downloads: max_downloads
+                .filter(|limit| *limit > 0)
+                .map(|limit| Arc::new(Semaphore::new(limit))),
+            bandwidth: bytes_per_second
+                .filter(|limit| *limit > 0)
+                .map(|limit| Arc::new(Bandwidth::new(limit))),
+        }
+    }
+
+    #[must_use]
+    /// Get the limits shared by every download in this process, from the Scoop config
+    ///
+    /// The limits are read from the config the first time this is called
+    pub fn shared(config: &config::Scoop) -> Self {
+        static SHARED: OnceLock<DownloadLimits> = OnceLock::new();
+
+        SHARED.get_or_init(|| Self::from(config)).clone()
+    }
+
+    /// Wait until another download is allowed to start
+    ///
+    /// The download may continue until the returned permit is dropped
+    pub async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        let downloads = self.downloads.clone()?;
+
+        // The semaphore is never closed
+        downloads.acquire_owned().await.ok()
+    }
+
+    /// Wait until the bytes can be downloaded without exceeding the bandwidth limit
+    pub async fn throttle(&self, bytes: u64) {
+        if let Some(bandwidth) = &self.bandwidth {
+            let delay = bandwidth.reserve(bytes);
+
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+/// A token bucket, which allows up to a second of bandwidth to be used in a burst
+struct Bandwidth {
+    bytes_per_second: u64,
+    state: Mutex<BandwidthState>,
+}
+
+#[derive(Debug)]
+struct BandwidthState {
+    /// The bytes that can be downloaded immediately, which is negative if downloads are ahead of the limit
+    available: f64,
+    last_refill: Instant,
+}
+
+#[allow(clippy::cast_precision_loss)]
+impl Bandwidth {
+    fn new(bytes_per_second: u64) -> Self {
+        Self {
+            bytes_per_second,
+            state: Mutex::new(BandwidthState {
+                available: bytes_per_second as f64,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// Reserve the bytes, and get how long to wait before they can be downloaded
+    fn reserve(&self, bytes: u64) -> Duration {
+        let rate = self.bytes_per_second as f64;
+        let mut state = self.state.lock();
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+        state.available = (state.available + elapsed * rate).min(rate);
+        state.last_refill = now;
+
+        state.available -= bytes as f64;
+
+        if state.available < 0.0 {
+            Duration::from_secs_f64(-state.available / rate)
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bandwidth_reservations() {
+        let bandwidth = Bandwidth::new(1000);
+
+        // The first second can be used in a burst
+        assert_eq!(bandwidth.reserve(1000), Duration::ZERO);
+
+        let delay = bandwidth.reserve(500);
+        assert!(delay > Duration::from_millis(450) && delay <= Duration::from_millis(500));
+
+        // Later reservations wait behind earlier ones
+        let delay = bandwidth.reserve(500);
+        assert!(delay > Duration::from_millis(950) && delay <= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_download_permits() {
+        let limits = DownloadLimits::new(Some(1), None);
+
+        let permit = limits.acquire().await;
+        assert!(permit.is_some());
+
+        let cloned = limits.clone();
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), cloned.acquire())
+                .await
+                .is_err(),
+            "the permit should be shared between clones"
+        );
+
+        drop(permit);
+        assert!(limits.acquire().await.is_some());
+
+        assert!(DownloadLimits::default().acquire().await.is_none());
+    }
+}
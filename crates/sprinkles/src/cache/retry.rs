@@ -0,0 +1,144 @@
+//! Retrying failed downloads
+
+use std::time::Duration;
+
+use reqwest::{
+    header::{HeaderMap, RETRY_AFTER},
+    StatusCode,
+};
+
+use crate::config;
+
+/// The longest delay between retries, when backing off
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// The longest delay requested by a `Retry-After` header that will be honored
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How to retry downloads after transient failures
+pub struct RetryPolicy {
+    /// The maximum number of retries
+    pub retries: u32,
+    /// The delay before the first retry, which doubles after each attempt
+    pub delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl From<&config::Scoop> for RetryPolicy {
+    fn from(config: &config::Scoop) -> Self {
+        let default = Self::default();
+
+        Self {
+            retries: config.download_retries.unwrap_or(default.retries),
+            delay: config
+                .download_retry_delay
+                .map_or(default.delay, Duration::from_millis),
+        }
+    }
+}
+
+impl RetryPolicy {
+    #[must_use]
+    /// A policy that never retries
+    pub fn none() -> Self {
+        Self {
+            retries: 0,
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    /// Get the delay before the given retry attempt, starting from 0
+    ///
+    /// If the server requested a delay with `Retry-After`, it is used instead
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after.min(MAX_RETRY_AFTER);
+        }
+
+        self.delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(MAX_DELAY.max(self.delay))
+    }
+}
+
+#[must_use]
+/// Check if a response status is worth retrying
+pub fn is_transient_status(status: StatusCode) -> bool {
+    status.is_server_error()
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+#[must_use]
+/// Check if a request error is worth retrying, such as a timeout or a dropped connection
+pub fn is_transient_error(error: &reqwest::Error) -> bool {
+    error.is_timeout()
+        || error.is_connect()
+        || error.is_request()
+        || error.is_body()
+        || error.is_decode()
+}
+
+#[must_use]
+/// Parse the `Retry-After` header, as either a number of seconds or an HTTP date
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+
+    // A date in the past means the request can be retried immediately
+    Some(
+        (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::default();
+
+        assert_eq!(policy.delay(0, None), Duration::from_secs(1));
+        assert_eq!(policy.delay(2, None), Duration::from_secs(4));
+        assert_eq!(policy.delay(10, None), MAX_DELAY);
+        assert_eq!(
+            policy.delay(0, Some(Duration::from_secs(10))),
+            Duration::from_secs(10)
+        );
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(120)));
+
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+    }
+}
@@ -0,0 +1,218 @@
+//! Splitting downloads into segments, which are downloaded in parallel
+
+use std::{io::SeekFrom, ops::RangeInclusive, path::Path};
+
+use futures::StreamExt;
+use indicatif::ProgressBar;
+use reqwest::{
+    header::{self, HeaderMap},
+    Response, StatusCode,
+};
+use tokio::{
+    fs::OpenOptions,
+    io::{AsyncSeekExt, AsyncWriteExt},
+};
+
+use super::{limits::DownloadLimits, Error};
+use crate::config;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How to split downloads into segments
+pub struct SegmentPolicy {
+    /// The maximum number of connections to use for a single download
+    pub connections: u32,
+    /// The smallest segment, in bytes, to split a download into
+    pub min_segment_size: u64,
+}
+
+impl Default for SegmentPolicy {
+    fn default() -> Self {
+        Self {
+            connections: 1,
+            min_segment_size: 5 * 1024 * 1024,
+        }
+    }
+}
+
+impl From<&config::Scoop> for SegmentPolicy {
+    fn from(config: &config::Scoop) -> Self {
+        let default = Self::default();
+
+        Self {
+            connections: config.download_segments.unwrap_or(default.connections),
+            min_segment_size: config
+                .download_min_segment_size
+                .unwrap_or(default.min_segment_size),
+        }
+    }
+}
+
+impl SegmentPolicy {
+    #[must_use]
+    /// Split a file of the given length into the byte ranges to download
+    ///
+    /// Returns a single range if the file is too small to be split
+    pub fn ranges(&self, length: u64) -> Vec<RangeInclusive<u64>> {
+        if length == 0 {
+            return vec![];
+        }
+
+        let count =
+            (length / self.min_segment_size.max(1)).clamp(1, u64::from(self.connections.max(1)));
+        let segment_size = length.div_ceil(count);
+
+        (0..count)
+            .map(|i| i * segment_size)
+            .take_while(|start| *start < length)
+            .map(|start| start..=(start + segment_size).min(length) - 1)
+            .collect()
+    }
+
+    #[must_use]
+    /// Get the segments to download the response in, if the server supports range requests
+    /// and the file is large enough to be split
+    pub fn segments(&self, resp: &Response) -> Option<Vec<RangeInclusive<u64>>> {
+        if self.connections <= 1 || resp.status() != StatusCode::OK {
+            return None;
+        }
+
+        let accepts_ranges = resp
+            .headers()
+            .get(header::ACCEPT_RANGES)
+            .is_some_and(|value| value.as_bytes().eq_ignore_ascii_case(b"bytes"));
+
+        // Ranges refer to the encoded body, so they can't be used if the response is decoded
+        if !accepts_ranges || resp.headers().contains_key(header::CONTENT_ENCODING) {
+            return None;
+        }
+
+        let ranges = self.ranges(resp.content_length()?);
+
+        (ranges.len() > 1).then_some(ranges)
+    }
+}
+
+/// Download the response into the segments of a file, in parallel
+///
+/// The first segment is read from the response itself,
+/// and the rest are requested from the same url with range requests, sending the given headers.
+///
+/// # Errors
+/// - A segment request fails
+/// - The server does not respond with the requested range
+/// - Writing to the file fails
+pub async fn download(
+    client: &reqwest::Client,
+    resp: Response,
+    ranges: Vec<RangeInclusive<u64>>,
+    headers: HeaderMap,
+    limits: &DownloadLimits,
+    path: &Path,
+    pb: Option<&ProgressBar>,
+) -> Result<(), Error> {
+    let length = ranges.last().map_or(0, |range| range.end() + 1);
+
+    // Preallocate the file, so each segment can be written in place
+    let file = tokio::fs::File::create(path).await?;
+    file.set_len(length).await?;
+    drop(file);
+
+    // Request the rest of the segments from the final url, after any redirects
+    let url = resp.url().clone();
+
+    let mut ranges = ranges.into_iter();
+    let first = ranges.next().expect("at least one segment");
+
+    let rest = ranges.map(|range| {
+        let url = url.clone();
+        let headers = headers.clone();
+
+        async move {
+            let resp = client
+                .get(url)
+                .headers(headers)
+                .header(
+                    header::RANGE,
+                    format!("bytes={}-{}", range.start(), range.end()),
+                )
+                .send()
+                .await?;
+
+            if resp.status() != StatusCode::PARTIAL_CONTENT
+                || super::content_range_start(&resp) != Some(*range.start())
+            {
+                return Err(Error::ErrorCode(resp.status()));
+            }
+
+            write(resp, range, limits, path, pb).await
+        }
+    });
+
+    futures::future::try_join_all(
+        std::iter::once(futures::future::Either::Left(write(
+            resp, first, limits, path, pb,
+        )))
+        .chain(rest.map(futures::future::Either::Right)),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Write the range of the file from the response body, ignoring anything after the range
+async fn write(
+    resp: Response,
+    range: RangeInclusive<u64>,
+    limits: &DownloadLimits,
+    path: &Path,
+    pb: Option<&ProgressBar>,
+) -> Result<(), Error> {
+    let mut file = OpenOptions::new().write(true).open(path).await?;
+    file.seek(SeekFrom::Start(*range.start())).await?;
+
+    let mut remaining = range.end() - range.start() + 1;
+    let mut stream = resp.bytes_stream();
+
+    while remaining > 0 {
+        let Some(chunk) = stream.next().await else {
+            return Err(Error::IncompleteSegment);
+        };
+        let chunk = chunk?;
+
+        #[allow(clippy::cast_possible_truncation)]
+        let chunk = &chunk[..remaining.min(chunk.len() as u64) as usize];
+        limits.throttle(chunk.len() as u64).await;
+        file.write_all(chunk).await?;
+
+        remaining -= chunk.len() as u64;
+
+        if let Some(pb) = pb {
+            pb.inc(chunk.len() as u64);
+        }
+    }
+
+    file.flush().await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranges() {
+        let policy = SegmentPolicy {
+            connections: 4,
+            min_segment_size: 10,
+        };
+
+        assert!(policy.ranges(0).is_empty());
+        assert_eq!(policy.ranges(15), vec![0..=14]);
+        assert_eq!(policy.ranges(25), vec![0..=12, 13..=24]);
+        assert_eq!(
+            policy.ranges(1000),
+            vec![0..=249, 250..=499, 500..=749, 750..=999]
+        );
+    }
+}
@@ -0,0 +1,605 @@
+//! Discover the latest upstream version of a package from its `checkver` configuration
+
+use std::collections::HashMap;
+
+use regex::{Captures, Regex};
+use reqwest::header;
+use serde_json::Value;
+
+use crate::{
+    hash::{
+        formats::{json, xml},
+        substitutions::Substitute,
+    },
+    offline,
+    packages::{
+        models::manifest::{Checkver, SourceforgeUnion},
+        Manifest,
+    },
+    requests::{self, Client, ClientLike},
+    version::Version,
+};
+
+/// The default GitHub API base url
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+
+/// The default `SourceForge` base url
+pub const SOURCEFORGE_URL: &str = "https://sourceforge.net";
+
+/// The regex Scoop uses to find the version in a GitHub release response
+const GITHUB_REGEX: &str = r"/releases/tag/(?:v|V)?([\d.]+)";
+
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+/// Checkver errors
+pub enum Error {
+    #[error("Manifest does not have a `checkver` field")]
+    MissingCheckver,
+    #[error("PowerShell checkver scripts are not supported")]
+    UnsupportedScript,
+    #[error("Checkver does not have a url, and the manifest does not have a homepage")]
+    MissingUrl,
+    #[error("Checkver does not have a regex, jsonpath or xpath to find the version with")]
+    MissingExtractor,
+    #[error("Invalid GitHub repository: {0}")]
+    InvalidGithubRepo(String),
+    #[error("Invalid SourceForge project: {0}")]
+    InvalidSourceforgeProject(String),
+    #[error("HTTP error: {0}")]
+    Reqwest(#[from] reqwest::Error),
+    #[error("Invalid regex: {0}")]
+    Regex(#[from] regex::Error),
+    #[error("Error parsing json: {0}")]
+    SerdeJson(#[from] serde_json::Error),
+    #[error("Json error: {0}")]
+    Json(#[from] json::Error),
+    #[error("XML error: {0}")]
+    Xml(#[from] xml::XMLError),
+    #[error("Could not find a version matching the checkver configuration")]
+    NotFound,
+    #[error("Cannot check for new versions while offline")]
+    Offline,
+}
+
+/// Checkver result type
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Clone)]
+/// The latest version found by a checkver
+pub struct Checked {
+    /// The latest version
+    pub version: Version,
+    /// The regex captures, as Scoop's `$match` variables (e.g `$match1`, `$matchBuild`)
+    ///
+    /// These are substituted into the autoupdate config alongside the version
+    pub matches: HashMap<String, String>,
+}
+
+impl From<Version> for Checked {
+    fn from(version: Version) -> Self {
+        Self {
+            version,
+            matches: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Evaluates checkver configurations
+pub struct Checker {
+    client: Option<reqwest::Client>,
+    github_api_url: String,
+    github_token: Option<String>,
+    sourceforge_url: String,
+}
+
+impl Default for Checker {
+    fn default() -> Self {
+        Self {
+            client: None,
+            github_api_url: GITHUB_API_URL.to_string(),
+            github_token: None,
+            sourceforge_url: SOURCEFORGE_URL.to_string(),
+        }
+    }
+}
+
+/// The normalized checkver configuration
+struct Source {
+    url: String,
+    regex: Option<String>,
+    jsonpath: Option<String>,
+    xpath: Option<String>,
+    reverse: bool,
+    replace: Option<String>,
+    useragent: Option<String>,
+}
+
+impl Checker {
+    #[must_use]
+    /// Create a new checker with the default client and base urls
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    /// Use the given client for requests, instead of the default SFSU client
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    #[must_use]
+    /// Use the given base url for the GitHub API, instead of [`GITHUB_API_URL`]
+    pub fn with_github_api_url(mut self, url: impl Into<String>) -> Self {
+        self.github_api_url = url.into();
+        self
+    }
+
+    #[must_use]
+    /// Authenticate requests to GitHub with the given token, to avoid being rate limited
+    pub fn with_github_token(mut self, token: Option<String>) -> Self {
+        self.github_token = token;
+        self
+    }
+
+    #[must_use]
+    /// Use the given base url for `SourceForge`, instead of [`SOURCEFORGE_URL`]
+    pub fn with_sourceforge_url(mut self, url: impl Into<String>) -> Self {
+        self.sourceforge_url = url.into();
+        self
+    }
+
+    /// Find the latest version of the package, using its `checkver` configuration
+    ///
+    /// The regex captures are returned alongside the version, to be used in the autoupdate config
+    ///
+    /// # Errors
+    /// - The manifest does not have a `checkver` field
+    /// - The checkver configuration is invalid or uses a script
+    /// - The checkver source could not be fetched
+    /// - No version could be found in the source
+    /// - Offline mode is enabled
+    pub async fn check(&self, manifest: &Manifest) -> Result<Checked> {
+        if offline::enabled() {
+            return Err(Error::Offline);
+        }
+
+        let source = self.source(manifest)?;
+        let submap = manifest.version.submap();
+
+        let client = match &self.client {
+            Some(client) => client.clone(),
+            None => Client::asynchronous().client().clone(),
+        };
+
+        let mut request = client
+            .get(&source.url)
+            .headers(requests::auth_headers_with_api(
+                &source.url,
+                self.github_token.as_deref(),
+                Some(&self.github_api_url),
+            ));
+        if let Some(useragent) = &source.useragent {
+            request = request.header(header::USER_AGENT, useragent);
+        }
+
+        let body = request.send().await?.error_for_status()?.text().await?;
+
+        let text = if let Some(jsonpath) = &source.jsonpath {
+            let json: Value = serde_json::from_str(&body)?;
+            json::parse_json(&json, &submap, jsonpath)?
+        } else if let Some(xpath) = &source.xpath {
+            xml::parse_xml(&body, &submap, xpath)?
+        } else {
+            body
+        };
+
+        let Some(regex) = &source.regex else {
+            return Ok(Version::new(text.trim()).into());
+        };
+
+        extract_version(
+            &text,
+            &Regex::new(regex)?,
+            source.reverse,
+            source.replace.as_deref(),
+        )
+    }
+
+    fn source(&self, manifest: &Manifest) -> Result<Source> {
+        let checkver = manifest
+            .install_config
+            .checkver
+            .as_ref()
+            .ok_or(Error::MissingCheckver)?;
+        let homepage = manifest.homepage.clone().ok_or(Error::MissingUrl);
+
+        let class = match checkver {
+            Checkver::String(checkver) if checkver.eq_ignore_ascii_case("github") => {
+                return self.github_source(&homepage?, None);
+            }
+            Checkver::String(regex) => {
+                return Ok(Source {
+                    url: homepage?,
+                    regex: Some(regex.clone()),
+                    jsonpath: None,
+                    xpath: None,
+                    reverse: false,
+                    replace: None,
+                    useragent: None,
+                });
+            }
+            Checkver::CheckverClass(class) => class,
+        };
+
+        if class.script.is_some() {
+            return Err(Error::UnsupportedScript);
+        }
+
+        let regex = class.regex.clone().or_else(|| class.re.clone());
+
+        let mut source = if let Some(repo) = &class.github {
+            self.github_source(repo, regex)?
+        } else if let Some(sourceforge) = &class.sourceforge {
+            self.sourceforge_source(sourceforge, regex)?
+        } else {
+            let url = match &class.url {
+                Some(url) => url
+                    .clone()
+                    .into_substituted(&manifest.version.submap(), false),
+                None => homepage?,
+            };
+
+            let jsonpath = class.jsonpath.clone().or_else(|| class.jp.clone());
+
+            if regex.is_none() && jsonpath.is_none() && class.xpath.is_none() {
+                return Err(Error::MissingExtractor);
+            }
+
+            Source {
+                url,
+                regex,
+                jsonpath,
+                xpath: class.xpath.clone(),
+                reverse: false,
+                replace: None,
+                useragent: None,
+            }
+        };
+
+        source.reverse = class.reverse.unwrap_or_default();
+        source.replace.clone_from(&class.replace);
+        source.useragent.clone_from(&class.useragent);
+
+        Ok(source)
+    }
+
+    fn github_source(&self, repo: &str, regex: Option<String>) -> Result<Source> {
+        let repo_path = repo
+            .trim_end_matches('/')
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .trim_start_matches("github.com/");
+
+        let Some((owner, name)) = repo_path.split_once('/') else {
+            return Err(Error::InvalidGithubRepo(repo.to_string()));
+        };
+
+        if name.contains('/') {
+            return Err(Error::InvalidGithubRepo(repo.to_string()));
+        }
+
+        Ok(Source {
+            url: format!(
+                "{}/repos/{owner}/{name}/releases/latest",
+                self.github_api_url.trim_end_matches('/')
+            ),
+            // Like Scoop, the regex is matched against the raw release JSON, which includes the release page url
+            regex: Some(regex.unwrap_or_else(|| GITHUB_REGEX.to_string())),
+            jsonpath: None,
+            xpath: None,
+            reverse: false,
+            replace: None,
+            useragent: None,
+        })
+    }
+
+    fn sourceforge_source(
+        &self,
+        sourceforge: &SourceforgeUnion,
+        regex: Option<String>,
+    ) -> Result<Source> {
+        let (project, path) = match sourceforge {
+            SourceforgeUnion::String(project) => match project.split_once('/') {
+                Some((project, path)) => (project.to_string(), path.to_string()),
+                None => (project.clone(), String::new()),
+            },
+            SourceforgeUnion::SourceforgeClass(class) => (
+                class
+                    .project
+                    .clone()
+                    .ok_or_else(|| Error::InvalidSourceforgeProject(format!("{class:?}")))?,
+                class.path.clone().unwrap_or_default(),
+            ),
+        };
+
+        if project.is_empty() {
+            return Err(Error::InvalidSourceforgeProject(project));
+        }
+
+        let path = path.trim_matches('/');
+
+        Ok(Source {
+            url: format!(
+                "{}/projects/{project}/rss?path=/{path}",
+                self.sourceforge_url.trim_end_matches('/')
+            ),
+            regex: Some(regex.unwrap_or_else(|| {
+                format!(
+                    r"CDATA\[/{}/?[^\]]*?(?<version>[\d.]+)",
+                    regex::escape(path)
+                )
+            })),
+            jsonpath: None,
+            xpath: None,
+            reverse: false,
+            replace: None,
+            useragent: None,
+        })
+    }
+}
+
+/// Find the version in the text using the regex
+///
+/// The `version` capture group is used if present, otherwise the first capture group, otherwise the whole match.
+/// If `replace` is provided, it is expanded using the captures instead.
+fn extract_version(
+    text: &str,
+    regex: &Regex,
+    reverse: bool,
+    replace: Option<&str>,
+) -> Result<Checked> {
+    let captures = if reverse {
+        regex.captures_iter(text).last()
+    } else {
+        regex.captures(text)
+    }
+    .ok_or(Error::NotFound)?;
+
+    let matches = capture_matches(regex, &captures);
+
+    if let Some(replace) = replace {
+        let mut version = String::new();
+        captures.expand(replace, &mut version);
+
+        return Ok(Checked {
+            version: Version::new(version),
+            matches,
+        });
+    }
+
+    let version = captures
+        .name("version")
+        .or_else(|| captures.get(1))
+        .or_else(|| captures.get(0))
+        .ok_or(Error::NotFound)?;
+
+    Ok(Checked {
+        version: Version::new(version.as_str()),
+        matches,
+    })
+}
+
+/// Get the regex captures as Scoop's `$match` variables
+///
+/// Named groups are title cased (i.e `(?<build>...)` is `$matchBuild`),
+/// and unnamed groups use their index (i.e `$match1`).
+/// The whole match is not included.
+fn capture_matches(regex: &Regex, captures: &Captures<'_>) -> HashMap<String, String> {
+    regex
+        .capture_names()
+        .enumerate()
+        .skip(1)
+        .filter_map(|(index, name)| {
+            let value = captures.get(index)?.as_str().to_string();
+
+            let name = match name {
+                Some(name) => {
+                    let mut chars = name.chars();
+                    chars
+                        .next()
+                        .map(|first| first.to_uppercase().chain(chars).collect())
+                        .unwrap_or_default()
+                }
+                None => index.to_string(),
+            };
+
+            Some((format!("$match{name}"), value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        packages::CreateManifest,
+        test_server::{Response, TestServer},
+    };
+
+    fn manifest(json: &str) -> Manifest {
+        Manifest::from_str(json.to_string()).unwrap()
+    }
+
+    const RELEASE_JSON: &str = r#"{
+        "html_url": "https://github.com/winpax/sfsu/releases/tag/v1.14.0",
+        "tag_name": "v1.14.0",
+        "assets": [{ "name": "sfsu-1.14.1-x86_64.zip" }]
+    }"#;
+
+    fn checker(server: &TestServer) -> Checker {
+        Checker::new()
+            .with_client(reqwest::Client::new())
+            .with_github_api_url(server.url(""))
+            .with_sourceforge_url(server.url(""))
+    }
+
+    #[test]
+    fn test_extract_version() {
+        let regex = Regex::new(r"v([\d.]+)").unwrap();
+
+        let checked = extract_version("v1.0 v2.0", &regex, false, None).unwrap();
+        assert_eq!(checked.version.as_str(), "1.0");
+        assert_eq!(checked.matches["$match1"], "1.0");
+
+        let checked = extract_version("v1.0 v2.0", &regex, true, None).unwrap();
+        assert_eq!(checked.version.as_str(), "2.0");
+
+        let regex = Regex::new(r"(?<major>\d+)_(?<minor>\d+)").unwrap();
+        let checked =
+            extract_version("app-3_14.zip", &regex, false, Some("${major}.${minor}")).unwrap();
+        assert_eq!(checked.version.as_str(), "3.14");
+        assert_eq!(checked.matches["$matchMajor"], "3");
+        assert_eq!(checked.matches["$matchMinor"], "14");
+        assert!(!checked.matches.contains_key("$match0"));
+
+        assert!(matches!(
+            extract_version("nothing here", &regex, false, None),
+            Err(Error::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_checkver_regex() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/download/1.0.0" => Response::ok("<a href=\"app-1.2.3.zip\">app-1.2.3.zip</a>"),
+            _ => Response::status(404),
+        });
+
+        let manifest = manifest(&format!(
+            r#"{{"version":"1.0.0","checkver":{{"url":"{}","regex":"app-([\\d.]+)\\.zip"}}}}"#,
+            server.url("/download/$version")
+        ));
+
+        let checked = checker(&server).check(&manifest).await.unwrap();
+
+        assert_eq!(checked.version.as_str(), "1.2.3");
+    }
+
+    #[tokio::test]
+    async fn test_checkver_matches() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/download" => Response::ok("<a href=\"app-1.2.3-b456.zip\">"),
+            _ => Response::status(404),
+        });
+
+        let manifest = manifest(&format!(
+            r#"{{"version":"1.0.0","checkver":{{"url":"{}","regex":"app-([\\d.]+)-b(?<build>\\d+)\\.zip"}}}}"#,
+            server.url("/download")
+        ));
+
+        let checked = checker(&server).check(&manifest).await.unwrap();
+
+        assert_eq!(checked.version.as_str(), "1.2.3");
+        assert_eq!(checked.matches["$match1"], "1.2.3");
+        assert_eq!(checked.matches["$matchBuild"], "456");
+    }
+
+    #[tokio::test]
+    async fn test_checkver_jsonpath() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/latest.json" => Response::ok(r#"{"releases":[{"version":"24.05-beta"}]}"#),
+            _ => Response::status(404),
+        });
+
+        let manifest = manifest(&format!(
+            r#"{{"version":"24.04","checkver":{{"url":"{}","jp":"$.releases[0].version"}}}}"#,
+            server.url("/latest.json")
+        ));
+
+        let checked = checker(&server).check(&manifest).await.unwrap();
+
+        assert_eq!(checked.version.as_str(), "24.05-beta");
+    }
+
+    #[tokio::test]
+    async fn test_checkver_github() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/repos/winpax/sfsu/releases/latest" => Response::ok(RELEASE_JSON),
+            _ => Response::status(404),
+        });
+
+        let manifest = manifest(
+            r#"{"version":"1.13.1","homepage":"https://github.com/winpax/sfsu","checkver":"github"}"#,
+        );
+
+        let checked = checker(&server).check(&manifest).await.unwrap();
+
+        assert_eq!(checked.version.as_str(), "1.14.0");
+    }
+
+    #[tokio::test]
+    async fn test_checkver_github_custom_regex() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/repos/winpax/sfsu/releases/latest" => Response::ok(RELEASE_JSON),
+            _ => Response::status(404),
+        });
+
+        let manifest = manifest(
+            r#"{"version":"1.13.1","checkver":{"github":"https://github.com/winpax/sfsu","regex":"sfsu-([\\d.]+)-x86_64\\.zip"}}"#,
+        );
+
+        let checked = checker(&server).check(&manifest).await.unwrap();
+
+        assert_eq!(checked.version.as_str(), "1.14.1");
+    }
+
+    #[tokio::test]
+    async fn test_checkver_github_api_url_is_authenticated() {
+        let server = TestServer::start(|request| match request.header("authorization") {
+            Some("Bearer ghp_token") => Response::ok(RELEASE_JSON),
+            _ => Response::status(401),
+        });
+
+        let manifest = manifest(
+            r#"{"version":"1.13.1","homepage":"https://github.com/winpax/sfsu","checkver":"github"}"#,
+        );
+
+        let checked = checker(&server)
+            .with_github_token(Some("ghp_token".to_string()))
+            .check(&manifest)
+            .await
+            .unwrap();
+
+        assert_eq!(checked.version.as_str(), "1.14.0");
+    }
+
+    #[tokio::test]
+    async fn test_checkver_useragent() {
+        let server = TestServer::start(|request| match request.header("user-agent") {
+            Some("custom-agent") => Response::ok("version 2.0"),
+            _ => Response::status(403),
+        });
+
+        let manifest = manifest(&format!(
+            r#"{{"version":"1.0","checkver":{{"url":"{}","re":"version ([\\d.]+)","useragent":"custom-agent"}}}}"#,
+            server.url("/")
+        ));
+
+        let checked = checker(&server).check(&manifest).await.unwrap();
+
+        assert_eq!(checked.version.as_str(), "2.0");
+    }
+
+    #[test]
+    fn test_script_unsupported() {
+        let manifest = manifest(r#"{"version":"1.0","checkver":{"script":"Get-Version"}}"#);
+
+        assert!(matches!(
+            Checker::new().source(&manifest),
+            Err(Error::UnsupportedScript)
+        ));
+    }
+}
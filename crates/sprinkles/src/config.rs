@@ -1,6 +1,6 @@
 //! Scoop config helpers
 
-use std::{fmt::Display, path::PathBuf};
+use std::{fmt::Display, path::PathBuf, sync::OnceLock};
 
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
@@ -8,6 +8,19 @@ use serde_with::skip_serializing_none;
 
 use crate::{proxy::Proxy, Architecture};
 
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+/// Config errors
+pub enum Error {
+    #[error("Invalid value for `{key}`: {source}")]
+    InvalidValue {
+        key: String,
+        source: serde_json::Error,
+    },
+    #[error("Serializing config: {0}")]
+    Serde(#[from] serde_json::Error),
+}
+
 pub mod branch;
 
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -50,6 +63,8 @@ impl Display for ScoopRepo {
 }
 
 pub mod isolated;
+pub mod layers;
+pub mod mirror;
 
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -123,11 +138,17 @@ pub struct Scoop {
     pub shim: ScoopShim,
 
     #[serde(deserialize_with = "defaults::deserialize_scoop_root_path")]
-    #[serde(default = "defaults::default_scoop_root_path")]
+    #[serde(
+        default = "defaults::default_scoop_root_path",
+        skip_serializing_if = "defaults::is_default_scoop_root_path"
+    )]
     /// Path to Scoop root directory
     pub root_path: PathBuf,
 
-    #[serde(default = "defaults::default_scoop_global_path")]
+    #[serde(
+        default = "defaults::default_scoop_global_path",
+        skip_serializing_if = "defaults::is_default_scoop_global_path"
+    )]
     /// Path to Scoop root directory for global apps
     pub global_path: PathBuf,
 
@@ -166,6 +187,47 @@ pub struct Scoop {
     /// This is useful when you want to isolate Scoop from the system `PATH`.
     pub use_isolated_path: Option<isolated::IsolatedPath>,
 
+    /// The maximum number of times to retry a download after a transient failure,
+    /// such as a server error, timeout or dropped connection
+    ///
+    /// Defaults to 3
+    pub download_retries: Option<u32>,
+
+    /// The delay in milliseconds before retrying a failed download, which doubles after each attempt
+    ///
+    /// If the server sends a `Retry-After` header, that is used instead
+    ///
+    /// Defaults to 1000
+    pub download_retry_delay: Option<u64>,
+
+    /// The number of connections used to download a single file,
+    /// if the server supports range requests
+    ///
+    /// Defaults to 1, which downloads each file in a single request
+    pub download_segments: Option<u32>,
+
+    /// The smallest segment, in bytes, that a download will be split into
+    ///
+    /// Defaults to 5 MiB
+    pub download_min_segment_size: Option<u64>,
+
+    /// The maximum number of files to download at once
+    ///
+    /// Defaults to no limit
+    pub download_concurrency: Option<u32>,
+
+    /// The maximum combined download speed, in bytes per second
+    ///
+    /// Defaults to no limit
+    pub download_bandwidth_limit: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "skips::skip")]
+    /// Mirrors to download from, in order, when downloading from the original url fails
+    ///
+    /// Each mirror has a regex `pattern` that is matched against the download url,
+    /// and a `replacement` url that can reference the pattern's capture groups
+    pub download_mirrors: Vec<mirror::Mirror>,
+
     /// The timestamp of the last scoop update
     pub(crate) last_update: Option<String>,
 
@@ -174,14 +236,26 @@ pub struct Scoop {
     other: Map<String, Value>,
 }
 
+impl Default for Scoop {
+    /// The configuration Scoop uses when the config file is empty
+    fn default() -> Self {
+        serde_json::from_str("{}").expect("empty config is valid")
+    }
+}
+
 impl Scoop {
-    /// Converts the config path into the [`Scoop`] struct
+    /// Load the user config file
+    ///
+    /// This is the file that [`Scoop::save`] writes to.
+    /// Use [`Scoop::load_layered`] to include the other config layers.
     ///
     /// # Errors
+    /// - The config directory could not be found
+    /// - The config file does not exist
     /// - The file was not valid UTF-8
     /// - The read file was did not match the expected structure
     pub fn load() -> std::io::Result<Self> {
-        let config_path = Self::get_path();
+        let config_path = Self::io_path()?;
 
         let config = std::fs::read_to_string(config_path)?;
 
@@ -190,18 +264,60 @@ impl Scoop {
         Ok(config)
     }
 
+    /// Load the config resolved from every layer, with the layer that set each value
+    ///
+    /// See [`layers`] for the layers and their priority.
+    /// The resolved config should not be saved, as that would copy values from the other layers into the user config file.
+    ///
+    /// # Errors
+    /// - The current directory could not be read
+    /// - Any config file could not be read, or did not match the expected structure
+    pub fn load_layered() -> Result<layers::Layered, layers::Error> {
+        layers::Layers::discover()?.resolve()
+    }
+
+    #[must_use]
+    /// Get the config shared by this process, for code that is not given a context's config
+    ///
+    /// The config is resolved from every layer the first time this is called,
+    /// falling back to the default config if it cannot be loaded
+    pub(crate) fn shared() -> &'static Self {
+        static SHARED: OnceLock<Scoop> = OnceLock::new();
+
+        SHARED.get_or_init(|| {
+            Self::load_layered()
+                .map(layers::Layered::into_config)
+                .unwrap_or_default()
+        })
+    }
+
+    #[must_use]
+    /// Gets the scoop config path, whether or not the file exists
+    ///
+    /// Returns [`None`] if the config directory could not be found
+    pub fn path() -> Option<PathBuf> {
+        crate::env::paths::config_dir()
+            .map(|config_dir| config_dir.join("scoop").join("config.json"))
+    }
+
+    /// Gets the scoop config path, or an error if the config directory could not be found
+    fn io_path() -> std::io::Result<PathBuf> {
+        Self::path().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "Could not find config directory",
+            )
+        })
+    }
+
     #[must_use]
     /// Gets the scoop config path
     ///
     /// # Panics
     /// - The config directory does not exist
+    /// - The config file does not exist
     pub fn get_path() -> PathBuf {
-        let config_dir = crate::env::paths::config_dir();
-
-        let path = config_dir
-            .expect("Could not find config directory")
-            .join("scoop")
-            .join("config.json");
+        let path = Self::path().expect("Could not find config directory");
 
         assert!(path.exists(), "Could not find config file");
 
@@ -217,11 +333,18 @@ impl Scoop {
 
     /// Save the modified scoop config
     ///
+    /// The config file is created if it does not exist
+    ///
     /// # Errors
+    /// - The config directory could not be found
     /// - The struct could not be serialized to JSON
     /// - The file could not be written
     pub fn save(&self) -> std::io::Result<()> {
-        let config_path = Self::get_path();
+        let config_path = Self::io_path()?;
+
+        if let Some(parent) = config_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
 
         let config = serde_json::to_string_pretty(self)?;
 
@@ -230,6 +353,77 @@ impl Scoop {
         Ok(())
     }
 
+    /// Get the config as a map of keys to values
+    ///
+    /// Keys that are unset, or set to their default value, are omitted
+    ///
+    /// # Errors
+    /// - The config could not be serialized
+    pub fn to_map(&self) -> Result<Map<String, Value>, Error> {
+        Ok(serde_json::from_value(serde_json::to_value(self)?)?)
+    }
+
+    /// Get the value of a config key
+    ///
+    /// Returns [`None`] if the key is unset, or set to its default value
+    ///
+    /// # Errors
+    /// - The config could not be serialized
+    pub fn get(&self, key: &str) -> Result<Option<Value>, Error> {
+        Ok(self.to_map()?.remove(key))
+    }
+
+    /// Set a config key from a string value
+    ///
+    /// The value is parsed as JSON if the key accepts it (i.e numbers and booleans), and as a string otherwise.
+    /// Known keys are validated against their type, and unknown keys are kept as is.
+    ///
+    /// # Errors
+    /// - The value is not valid for the key
+    /// - The config could not be serialized
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        let mut map = self.to_map()?;
+
+        let mut try_value = |value: Value| {
+            map.insert(key.to_string(), value);
+            serde_json::from_value::<Self>(Value::Object(map.clone()))
+        };
+
+        let string = || Value::String(value.to_string());
+
+        // If neither value is valid, the error for the parsed value is more helpful
+        let config = match serde_json::from_str::<Value>(value) {
+            Ok(parsed) => try_value(parsed).or_else(|error| try_value(string()).map_err(|_| error)),
+            Err(_) => try_value(string()),
+        }
+        .map_err(|source| Error::InvalidValue {
+            key: key.to_string(),
+            source,
+        })?;
+
+        *self = config;
+
+        Ok(())
+    }
+
+    /// Remove a config key, resetting it to its default value
+    ///
+    /// Returns the previous value, or [`None`] if the key was not set
+    ///
+    /// # Errors
+    /// - The config could not be serialized
+    pub fn unset(&mut self, key: &str) -> Result<Option<Value>, Error> {
+        let mut map = self.to_map()?;
+
+        let Some(previous) = map.remove(key) else {
+            return Ok(None);
+        };
+
+        *self = serde_json::from_value(Value::Object(map))?;
+
+        Ok(Some(previous))
+    }
+
     /// Make the config strict
     ///
     /// This will remove all fields that are not in the config struct
@@ -245,7 +439,76 @@ mod skips;
 mod tests {
     use std::{env, path::PathBuf};
 
-    use super::isolated::IsolatedPath;
+    use super::{isolated::IsolatedPath, *};
+
+    #[test]
+    fn test_set_and_unset() {
+        let mut config = Scoop::default();
+
+        config.set("default_architecture", "arm64").unwrap();
+        assert_eq!(config.default_architecture, Architecture::Arm64);
+
+        config.set("download_retries", "5").unwrap();
+        assert_eq!(config.download_retries, Some(5));
+
+        config.set("proxy", "user:pass@localhost:8080").unwrap();
+        assert_eq!(
+            config.get("proxy").unwrap(),
+            Some(Value::String("user:pass@localhost:8080".into()))
+        );
+
+        // Values that look like JSON are kept as strings if the key expects a string
+        config.set("gh_token", "1234").unwrap();
+        assert_eq!(config.gh_token.as_deref(), Some("1234"));
+
+        config.set("aria2-enabled", "true").unwrap();
+        assert_eq!(
+            config.get("aria2-enabled").unwrap(),
+            Some(Value::Bool(true))
+        );
+
+        assert!(config.set("default_architecture", "x86").is_err());
+        assert!(config.set("download_retries", "-1").is_err());
+        assert!(config.set("shim", "unknown").is_err());
+        assert_eq!(config.download_retries, Some(5));
+
+        assert_eq!(
+            config.unset("download_retries").unwrap(),
+            Some(Value::from(5))
+        );
+        assert_eq!(config.download_retries, None);
+        assert_eq!(config.unset("download_retries").unwrap(), None);
+
+        assert!(config.unset("aria2-enabled").unwrap().is_some());
+        assert_eq!(config.get("aria2-enabled").unwrap(), None);
+    }
+
+    #[test]
+    fn test_default_paths_omitted() {
+        let mut config = Scoop::default();
+
+        assert_eq!(config.get("root_path").unwrap(), None);
+        assert_eq!(config.get("global_path").unwrap(), None);
+        assert_eq!(config.unset("root_path").unwrap(), None);
+
+        config.set("root_path", "/custom/scoop").unwrap();
+        assert_eq!(
+            config.unset("root_path").unwrap(),
+            Some(Value::String("/custom/scoop".into()))
+        );
+        assert_eq!(config.root_path, defaults::default_scoop_root_path());
+    }
+
+    #[test]
+    fn test_architecture_serde() {
+        assert_eq!(
+            serde_json::to_value(Architecture::Arm64).unwrap(),
+            Value::String("arm64".into())
+        );
+
+        let config: Scoop = serde_json::from_str(r#"{"default_architecture":"Arm64"}"#).unwrap();
+        assert_eq!(config.default_architecture, Architecture::Arm64);
+    }
 
     #[test]
     fn test_isolated_path_serde() {
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Deserializer};
 
@@ -16,6 +16,10 @@ pub fn default_scoop_root_path() -> PathBuf {
     path
 }
 
+pub fn is_default_scoop_root_path(path: &Path) -> bool {
+    path == default_scoop_root_path()
+}
+
 pub fn deserialize_scoop_root_path<'de, D>(deserializer: D) -> Result<PathBuf, D::Error>
 where
     D: Deserializer<'de>,
@@ -30,6 +34,7 @@ where
 /// as it causes too many issues when not running as admin
 ///
 /// This should be handled manually by implementations, when running as admin
+#[cfg(windows)]
 pub fn default_scoop_global_path() -> PathBuf {
     use std::{ffi::OsString, os::windows::ffi::OsStringExt};
 
@@ -58,3 +63,16 @@ pub fn default_scoop_global_path() -> PathBuf {
 
     path
 }
+
+/// Gets the default scoop path
+///
+/// There is no common application data folder outside of Windows,
+/// so a system-wide data directory is used instead
+#[cfg(not(windows))]
+pub fn default_scoop_global_path() -> PathBuf {
+    PathBuf::from("/var/lib/scoop")
+}
+
+pub fn is_default_scoop_global_path(path: &Path) -> bool {
+    path == default_scoop_global_path()
+}
@@ -0,0 +1,367 @@
+//! Layered config resolution
+//!
+//! The config is resolved from, in order of increasing priority:
+//! - The built-in defaults
+//! - The machine-wide config file
+//! - The user config file
+//! - The project config file, found in the current directory or any of its parents
+//! - Environment variables
+//!
+//! Layers are merged key by key, so a layer replaces the whole value of any key it sets.
+//!
+//! The project config file comes from whichever directory sfsu is run in,
+//! so it can only set the keys in [`PROJECT_KEYS`]. Any other keys in it are ignored.
+
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{Map, Value};
+
+use super::Scoop;
+
+/// The name of the project config file
+pub const PROJECT_CONFIG_FILE: &str = ".scoop.json";
+
+/// Keys that can be set in the project config file
+pub const PROJECT_KEYS: &[&str] = &[
+    "cache_path",
+    "default_architecture",
+    "download_mirrors",
+    "download_retries",
+    "download_retry_delay",
+    "download_segments",
+    "download_min_segment_size",
+    "download_concurrency",
+    "download_bandwidth_limit",
+];
+
+/// Environment variables that override config keys
+const ENV_KEYS: &[(&str, &str)] = &[
+    ("SCOOP", "root_path"),
+    ("SCOOP_GLOBAL", "global_path"),
+    ("SCOOP_CACHE", "cache_path"),
+    (crate::requests::GITHUB_TOKEN_ENV, "gh_token"),
+];
+
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+/// Errors resolving the layered config
+pub enum Error {
+    #[error("Could not read the current directory: {0}")]
+    CurrentDir(#[source] std::io::Error),
+    #[error("Could not read the config file at {}: {source}", path.display())]
+    Reading {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Invalid config file at {}: {source}", path.display())]
+    Parsing {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("Invalid config: {0}")]
+    Invalid(#[source] serde_json::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Where a config value was set
+pub enum Origin {
+    /// The built-in default value
+    Default,
+    /// The machine-wide config file
+    System(PathBuf),
+    /// The user config file
+    User(PathBuf),
+    /// The project config file
+    Project(PathBuf),
+    /// An environment variable
+    Env(&'static str),
+}
+
+impl Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::System(path) => write!(f, "system: {}", path.display()),
+            Self::User(path) => write!(f, "user: {}", path.display()),
+            Self::Project(path) => write!(f, "project: {}", path.display()),
+            Self::Env(var) => write!(f, "env: {var}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// The config files to resolve the config from
+///
+/// Files that do not exist are skipped
+pub struct Layers {
+    /// The machine-wide config file
+    pub system: Option<PathBuf>,
+    /// The user config file
+    pub user: Option<PathBuf>,
+    /// The project config file
+    pub project: Option<PathBuf>,
+}
+
+impl Layers {
+    /// Find the config files for the current environment
+    ///
+    /// # Errors
+    /// - The current directory could not be read
+    pub fn discover() -> Result<Self, Error> {
+        Ok(Self {
+            system: crate::env::paths::system_config_dir()
+                .map(|config_dir| config_dir.join("scoop").join("config.json")),
+            user: Scoop::path(),
+            project: find_project_config(&std::env::current_dir().map_err(Error::CurrentDir)?),
+        })
+    }
+
+    /// Get the config files that exist, in order of increasing priority
+    pub fn existing(&self) -> impl Iterator<Item = &Path> {
+        [&self.system, &self.user, &self.project]
+            .into_iter()
+            .filter_map(|path| path.as_deref())
+            .filter(|path| path.exists())
+    }
+
+    /// Resolve the config from every layer, including environment variables
+    ///
+    /// # Errors
+    /// - A config file could not be read
+    /// - A config file is not a JSON object, or has invalid values
+    pub fn resolve(&self) -> Result<Layered, Error> {
+        self.resolve_with_env(|var| std::env::var_os(var))
+    }
+
+    pub(crate) fn resolve_with_env(
+        &self,
+        env: impl Fn(&str) -> Option<OsString>,
+    ) -> Result<Layered, Error> {
+        let mut map = Map::new();
+        let mut origins = HashMap::new();
+
+        let files: [(_, fn(PathBuf) -> Origin); 3] = [
+            (self.system.as_ref(), Origin::System),
+            (self.user.as_ref(), Origin::User),
+            (self.project.as_ref(), Origin::Project),
+        ];
+
+        for (path, origin) in files {
+            let Some(path) = path.filter(|path| path.exists()) else {
+                continue;
+            };
+
+            let origin = origin(path.clone());
+            let mut layer = read_layer(path)?;
+
+            if let Origin::Project(_) = origin {
+                info!("Using project config file {}", path.display());
+
+                layer.retain(|key, _| {
+                    let allowed = PROJECT_KEYS.contains(&key.as_str());
+
+                    if !allowed {
+                        warn!(
+                            "Ignoring `{key}` in project config file {}. It can only be set in the user or system config",
+                            path.display()
+                        );
+                    }
+
+                    allowed
+                });
+            }
+
+            for (key, value) in layer {
+                origins.insert(key.clone(), origin.clone());
+                map.insert(key, value);
+            }
+        }
+
+        for &(var, key) in ENV_KEYS {
+            if let Some(value) = env(var).filter(|value| !value.is_empty()) {
+                origins.insert(key.to_string(), Origin::Env(var));
+                map.insert(
+                    key.to_string(),
+                    Value::String(value.to_string_lossy().into_owned()),
+                );
+            }
+        }
+
+        let config = serde_json::from_value(Value::Object(map)).map_err(Error::Invalid)?;
+
+        Ok(Layered { config, origins })
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A config resolved from every layer, with the origin of each value
+pub struct Layered {
+    config: Scoop,
+    origins: HashMap<String, Origin>,
+}
+
+impl Layered {
+    #[must_use]
+    /// Get the resolved config
+    pub fn config(&self) -> &Scoop {
+        &self.config
+    }
+
+    #[must_use]
+    /// Get the resolved config
+    pub fn into_config(self) -> Scoop {
+        self.config
+    }
+
+    #[must_use]
+    /// Get the layer that set the key
+    ///
+    /// Keys that were not set by any layer have the [`Origin::Default`] origin
+    pub fn origin(&self, key: &str) -> Origin {
+        self.origins.get(key).cloned().unwrap_or(Origin::Default)
+    }
+}
+
+/// Find the project config file in the directory or any of its parents
+fn find_project_config(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+/// Read a config file, which must contain a JSON object of valid config values
+fn read_layer(path: &Path) -> Result<Map<String, Value>, Error> {
+    let parsing = |source| Error::Parsing {
+        path: path.to_path_buf(),
+        source,
+    };
+
+    let contents = std::fs::read_to_string(path).map_err(|source| Error::Reading {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let layer: Map<String, Value> = serde_json::from_str(&contents).map_err(parsing)?;
+
+    // Every key is optional, so each layer must be a valid config on its own
+    serde_json::from_value::<Scoop>(Value::Object(layer.clone())).map_err(parsing)?;
+
+    Ok(layer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let project_dir = dir.join("project").join("nested");
+        std::fs::create_dir_all(&project_dir).unwrap();
+
+        let system = dir.join("system.json");
+        let user = dir.join("user.json");
+        let project = dir.join("project").join(PROJECT_CONFIG_FILE);
+
+        std::fs::write(
+            &system,
+            r#"{"download_retries":1,"cache_path":"/system/cache","aria2-enabled":true}"#,
+        )
+        .unwrap();
+        std::fs::write(&user, r#"{"download_retries":2,"gh_token":"user"}"#).unwrap();
+        std::fs::write(&project, r#"{"download_retries":3}"#).unwrap();
+
+        let layers = Layers {
+            system: Some(system.clone()),
+            user: Some(user),
+            project: find_project_config(&project_dir),
+        };
+        assert_eq!(layers.project.as_ref(), Some(&project));
+
+        let layered = layers
+            .resolve_with_env(|var| (var == "SCOOP_GH_TOKEN").then(|| "env".into()))
+            .unwrap();
+
+        assert_eq!(layered.config().download_retries, Some(3));
+        assert_eq!(layered.origin("download_retries"), Origin::Project(project));
+        assert_eq!(
+            layered.config().cache_path,
+            Some(PathBuf::from("/system/cache"))
+        );
+        assert_eq!(layered.origin("cache_path"), Origin::System(system));
+        assert_eq!(layered.config().gh_token.as_deref(), Some("env"));
+        assert_eq!(layered.origin("gh_token"), Origin::Env("SCOOP_GH_TOKEN"));
+        assert_eq!(
+            layered.config().get("aria2-enabled").unwrap(),
+            Some(Value::Bool(true))
+        );
+        assert_eq!(layered.origin("root_path"), Origin::Default);
+    }
+
+    #[test]
+    fn test_project_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user.json");
+        let project = dir.path().join(PROJECT_CONFIG_FILE);
+
+        std::fs::write(&user, r#"{"root_path":"/user/scoop"}"#).unwrap();
+        std::fs::write(
+            &project,
+            r#"{"root_path":"/project/scoop","proxy":"none","download_retries":3}"#,
+        )
+        .unwrap();
+
+        let layers = Layers {
+            user: Some(user.clone()),
+            project: Some(project.clone()),
+            ..Default::default()
+        };
+
+        let layered = layers.resolve_with_env(|_| None).unwrap();
+
+        assert_eq!(layered.config().root_path, PathBuf::from("/user/scoop"));
+        assert_eq!(layered.origin("root_path"), Origin::User(user));
+        assert_eq!(layered.config().proxy, None);
+        assert_eq!(layered.origin("download_retries"), Origin::Project(project));
+    }
+
+    #[test]
+    fn test_errors_name_the_invalid_layer() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user.json");
+        let project = dir.path().join(PROJECT_CONFIG_FILE);
+
+        std::fs::write(&user, r#"{"download_retries":2}"#).unwrap();
+        std::fs::write(&project, r#"{"download_retries":"many"}"#).unwrap();
+
+        let layers = Layers {
+            user: Some(user),
+            project: Some(project.clone()),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            layers.resolve_with_env(|_| None),
+            Err(Error::Parsing { path, .. }) if path == project
+        ));
+    }
+
+    #[test]
+    fn test_missing_layers_are_skipped() {
+        let layers = Layers {
+            system: Some(PathBuf::from("/does/not/exist.json")),
+            ..Default::default()
+        };
+
+        let layered = layers.resolve_with_env(|_| None).unwrap();
+
+        assert_eq!(layered.config().download_retries, None);
+        assert_eq!(layered.origin("download_retries"), Origin::Default);
+    }
+}
@@ -0,0 +1,50 @@
+//! Download mirror configuration
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A rule to rewrite download urls to a mirror
+pub struct Mirror {
+    /// A regex matched against the download url
+    pub pattern: String,
+    /// The url to download from instead, which may reference capture groups in the pattern (e.g. `$1`)
+    pub replacement: String,
+}
+
+impl Mirror {
+    #[must_use]
+    /// Rewrite the url to point to this mirror
+    ///
+    /// Returns [`None`] if the pattern does not match the url, or is not a valid regex
+    pub fn rewrite(&self, url: &str) -> Option<String> {
+        let regex = Regex::new(&self.pattern).ok()?;
+
+        if regex.is_match(url) {
+            Some(regex.replace(url, &self.replacement).into_owned())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite() {
+        let mirror = Mirror {
+            pattern: r"^https://github\.com/([^/]+/[^/]+)/releases/download/".into(),
+            replacement: "https://artifacts.example.com/github/$1/".into(),
+        };
+
+        assert_eq!(
+            mirror
+                .rewrite("https://github.com/owner/repo/releases/download/v1.0.0/app.zip")
+                .as_deref(),
+            Some("https://artifacts.example.com/github/owner/repo/v1.0.0/app.zip")
+        );
+        assert_eq!(mirror.rewrite("https://example.com/app.zip"), None);
+    }
+}
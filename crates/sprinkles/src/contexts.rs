@@ -5,16 +5,18 @@
 
 use std::{
     collections::HashMap,
-    ffi::OsStr,
+    ffi::{OsStr, OsString},
     fs::File,
     path::{Path, PathBuf},
 };
 
 use crate::{config, git};
 
+mod fixture;
 mod global;
 mod user;
 
+pub use fixture::{Fixture, FixtureBuilder};
 use futures::Future;
 pub use global::Global;
 use url::Url;
@@ -37,6 +39,25 @@ pub enum Error {
     JoinError(#[from] tokio::task::JoinError),
     #[error("Error reading known buckets: {0}")]
     SerdeJson(#[from] serde_json::Error),
+    #[error("Could not find the config directory. Make sure your home directory exists")]
+    MissingConfigDir,
+    #[error("Could not find any Scoop config file. Make sure Scoop is installed, or create the config file at {}", .0.display())]
+    MissingConfig(PathBuf),
+    #[error("Error loading the Scoop config: {0}")]
+    LoadingConfig(#[from] config::layers::Error),
+    #[error("The Scoop path from the SCOOP environment variable does not exist: {}. Update or unset the SCOOP environment variable", .0.display())]
+    MissingScoopEnvPath(PathBuf),
+    #[error("The Scoop root path does not exist: {}. Update `root_path` in the Scoop config, or set the SCOOP environment variable", .0.display())]
+    MissingRootPath(PathBuf),
+    #[error("The global Scoop path from the SCOOP_GLOBAL environment variable does not exist: {}. Update or unset the SCOOP_GLOBAL environment variable", .0.display())]
+    MissingGlobalEnvPath(PathBuf),
+    #[error("The global Scoop path does not exist: {}. Update `global_path` in the Scoop config, or set the SCOOP_GLOBAL environment variable", .0.display())]
+    MissingGlobalPath(PathBuf),
+    #[error("Error resolving the Scoop path {}: {source}", path.display())]
+    ResolvingPath {
+        path: PathBuf,
+        source: std::io::Error,
+    },
 
     #[error("{0}")]
     Custom(#[from] Box<dyn std::error::Error + Send + Sync>),
@@ -49,6 +70,30 @@ impl Error {
     }
 }
 
+/// Load the Scoop config from every layer, distinguishing missing config files from invalid ones
+///
+/// Any one of the config files is enough, so the user config file is not required if another layer exists
+fn load_config(
+    layers: &config::layers::Layers,
+    env: impl Fn(&str) -> Option<OsString>,
+) -> Result<config::Scoop, Error> {
+    if layers.existing().next().is_none() {
+        let path = layers.user.clone().ok_or(Error::MissingConfigDir)?;
+        return Err(Error::MissingConfig(path));
+    }
+
+    Ok(layers.resolve_with_env(env)?.into_config())
+}
+
+/// Resolve the real path to a context's directory, or the given error if it does not exist
+fn resolve_path(path: PathBuf, missing: impl FnOnce(PathBuf) -> Error) -> Result<PathBuf, Error> {
+    if !path.exists() {
+        return Err(missing(path));
+    }
+
+    dunce::canonicalize(&path).map_err(|source| Error::ResolvingPath { path, source })
+}
+
 /// An adapter for Scoop-like contexts
 ///
 /// This is used to provide a common interface for Scoop-like contexts, and to allow for mocking in tests
@@ -56,8 +101,10 @@ impl Error {
 /// Generally, you should not call this type directly, but instead use the [`User`] or [`Global`] types,
 /// or another type that implements [`ScoopContext`].
 ///
+/// For tests, use the [`Fixture`] type, which does not depend on a real Scoop install.
+///
 /// # Example
-/// ```
+/// ```no_run
 /// # use sprinkles::contexts::{ScoopContext, User};
 /// let context = User::new();
 /// let scoop_path = context.path();
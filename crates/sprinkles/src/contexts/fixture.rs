@@ -0,0 +1,344 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+use url::Url;
+
+use crate::{config, contexts::Error, git, packages::InstallManifest};
+
+use super::ScoopContext;
+
+#[derive(Debug, Clone)]
+/// A Scoop context rooted at an arbitrary directory, with an in-memory configuration
+///
+/// Unlike [`User`](super::User) and [`Global`](super::Global), this does not read the environment or the Scoop config file,
+/// so it can be used to run tests and tooling against a known Scoop install.
+///
+/// # Example
+/// ```no_run
+/// # use sprinkles::contexts::{Fixture, ScoopContext};
+/// let ctx = Fixture::builder(std::env::temp_dir().join("scoop"))
+///     .bucket_manifest("main", "sfsu", r#"{"version":"1.0.0"}"#)
+///     .build()
+///     .unwrap();
+///
+/// assert!(ctx.buckets_path().join("main").exists());
+/// ```
+pub struct Fixture {
+    config: config::Scoop,
+    path: PathBuf,
+}
+
+impl Fixture {
+    /// Construct a new fixture context at the given path
+    ///
+    /// The config's root and global paths are set to the fixture's path
+    ///
+    /// # Errors
+    /// - Creating the directory fails
+    pub fn new(path: impl AsRef<Path>, mut config: config::Scoop) -> std::io::Result<Self> {
+        let path = path.as_ref();
+
+        std::fs::create_dir_all(path)?;
+        let path = dunce::canonicalize(path)?;
+
+        config.root_path.clone_from(&path);
+        config.global_path.clone_from(&path);
+
+        Ok(Self { config, path })
+    }
+
+    #[must_use]
+    /// Construct a builder to lay out a fixture at the given path
+    pub fn builder(path: impl Into<PathBuf>) -> FixtureBuilder {
+        FixtureBuilder::new(path)
+    }
+}
+
+#[derive(Debug)]
+/// A builder for [`Fixture`] contexts
+///
+/// Nothing is written to disk until [`FixtureBuilder::build`] is called
+pub struct FixtureBuilder {
+    path: PathBuf,
+    config: config::Scoop,
+    dirs: Vec<PathBuf>,
+    files: Vec<(PathBuf, Vec<u8>)>,
+    known_buckets: BTreeMap<String, Url>,
+}
+
+impl FixtureBuilder {
+    #[must_use]
+    /// Construct a new fixture builder at the given path, with the default config
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            config: config::Scoop::default(),
+            dirs: vec![
+                PathBuf::from("apps"),
+                PathBuf::from("buckets"),
+                PathBuf::from("cache"),
+                PathBuf::from("persist"),
+            ],
+            files: vec![],
+            known_buckets: BTreeMap::new(),
+        }
+    }
+
+    #[must_use]
+    /// Use the given config for the fixture
+    pub fn config(mut self, config: config::Scoop) -> Self {
+        self.config = config;
+        self
+    }
+
+    #[must_use]
+    /// Add a directory, relative to the fixture's path
+    pub fn dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.dirs.push(path.into());
+        self
+    }
+
+    #[must_use]
+    /// Add a file, relative to the fixture's path
+    pub fn file(mut self, path: impl Into<PathBuf>, contents: impl Into<Vec<u8>>) -> Self {
+        self.files.push((path.into(), contents.into()));
+        self
+    }
+
+    #[must_use]
+    /// Add an empty bucket
+    pub fn bucket(self, name: &str) -> Self {
+        self.dir(Path::new("buckets").join(name).join("bucket"))
+    }
+
+    #[must_use]
+    /// Add a manifest to a bucket, creating the bucket if needed
+    pub fn bucket_manifest(self, bucket: &str, name: &str, manifest: impl Into<String>) -> Self {
+        self.file(
+            Path::new("buckets")
+                .join(bucket)
+                .join("bucket")
+                .join(format!("{name}.json")),
+            manifest.into(),
+        )
+    }
+
+    #[must_use]
+    /// Add a bucket to the list of known buckets, in the Scoop app's `buckets.json`
+    pub fn known_bucket(mut self, name: impl Into<String>, url: Url) -> Self {
+        self.known_buckets.insert(name.into(), url);
+        self
+    }
+
+    #[must_use]
+    /// Add an installed app, with its manifest and install manifest
+    ///
+    /// The app is laid out in both its version directory and the `current` directory
+    ///
+    /// # Panics
+    /// - The install manifest could not be serialized
+    pub fn app(
+        self,
+        name: &str,
+        version: &str,
+        manifest: impl Into<String>,
+        install: &InstallManifest,
+    ) -> Self {
+        let manifest = manifest.into();
+        let install = serde_json::to_string(install).expect("valid install manifest");
+
+        [version, "current"].into_iter().fold(self, |builder, dir| {
+            let app_dir = Path::new("apps").join(name).join(dir);
+
+            builder
+                .file(app_dir.join("manifest.json"), manifest.clone())
+                .file(app_dir.join("install.json"), install.clone())
+        })
+    }
+
+    #[must_use]
+    /// Add a file to the cache
+    pub fn cache_file(self, name: &str, contents: impl Into<Vec<u8>>) -> Self {
+        self.file(Path::new("cache").join(name), contents)
+    }
+
+    #[must_use]
+    /// Add a persisted file for an app
+    pub fn persist_file(
+        self,
+        app: &str,
+        path: impl AsRef<Path>,
+        contents: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.file(Path::new("persist").join(app).join(path), contents)
+    }
+
+    /// Lay out the fixture on disk, and construct the context
+    ///
+    /// Existing files in the fixture's path are kept, unless they are overwritten
+    ///
+    /// # Errors
+    /// - Creating any directory or file fails
+    /// - Serializing the known buckets fails
+    pub fn build(mut self) -> Result<Fixture, Error> {
+        if !self.known_buckets.is_empty() {
+            let buckets = serde_json::to_vec(&self.known_buckets)?;
+            self = self.file("apps/scoop/current/buckets.json", buckets);
+        }
+
+        let ctx = Fixture::new(&self.path, self.config)?;
+
+        for dir in self.dirs {
+            std::fs::create_dir_all(ctx.path.join(dir))?;
+        }
+
+        for (path, contents) in self.files {
+            let path = ctx.path.join(path);
+
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            std::fs::write(path, contents)?;
+        }
+
+        Ok(ctx)
+    }
+}
+
+impl ScoopContext<config::Scoop> for Fixture {
+    const CONTEXT_NAME: &'static str = "scoop";
+
+    fn config(&self) -> &config::Scoop {
+        &self.config
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn git_path() -> Result<PathBuf, which::Error> {
+        which::which("git")
+    }
+
+    fn apps_path(&self) -> PathBuf {
+        self.sub_path("apps")
+    }
+
+    fn buckets_path(&self) -> PathBuf {
+        self.sub_path("buckets")
+    }
+
+    /// Gets the fixture's cache path, from the config if it is set
+    fn cache_path(&self) -> PathBuf {
+        self.config
+            .cache_path
+            .clone()
+            .unwrap_or_else(|| self.sub_path("cache"))
+    }
+
+    fn persist_path(&self) -> PathBuf {
+        self.sub_path("persist")
+    }
+
+    fn shims_path(&self) -> PathBuf {
+        self.sub_path("shims")
+    }
+
+    fn workspace_path(&self) -> PathBuf {
+        self.sub_path("workspace")
+    }
+
+    fn logging_dir(&self) -> std::io::Result<PathBuf> {
+        let logs_path = self.path.join("logs");
+
+        std::fs::create_dir_all(&logs_path)?;
+
+        Ok(logs_path)
+    }
+
+    #[cfg(not(feature = "v2"))]
+    #[allow(deprecated)]
+    async fn new_log(&self) -> Result<File, Error> {
+        self.new_log_sync()
+    }
+
+    #[cfg(not(feature = "v2"))]
+    fn new_log_sync(&self) -> Result<File, Error> {
+        let logs_dir = self.logging_dir()?;
+        let date = Local::now();
+
+        let mut i = 0;
+        let file = loop {
+            i += 1;
+
+            let log_path =
+                logs_dir.join(format!("sfsu-{}-{i}.log", date.format("%Y-%m-%d-%H-%M-%S")));
+
+            if !log_path.exists() {
+                break File::create(log_path)?;
+            }
+        };
+
+        Ok(file)
+    }
+
+    /// Open the Scoop app repository, if the fixture has one
+    fn open_repo(&self) -> Option<git::Result<git::Repo>> {
+        self.context_app_path()
+            .join(".git")
+            .exists()
+            .then(|| git::Repo::scoop_app(self))
+    }
+
+    fn context_app_path(&self) -> PathBuf {
+        self.apps_path().join("scoop").join("current")
+    }
+
+    /// Fixtures are never outdated
+    async fn outdated(&self) -> Result<bool, Error> {
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixture_layout() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let ctx = Fixture::builder(dir.path())
+            .bucket("extras")
+            .bucket_manifest("main", "sfsu", r#"{"version":"1.0.0"}"#)
+            .known_bucket(
+                "main",
+                "https://github.com/ScoopInstaller/Main".parse().unwrap(),
+            )
+            .app(
+                "sfsu",
+                "1.0.0",
+                r#"{"version":"1.0.0"}"#,
+                &InstallManifest {
+                    bucket: Some("main".into()),
+                    ..Default::default()
+                },
+            )
+            .cache_file("sfsu#1.0.0#sfsu.zip", "zip")
+            .build()
+            .unwrap();
+
+        assert_eq!(ctx.config().root_path, ctx.path());
+        assert!(ctx.buckets_path().join("main/bucket/sfsu.json").exists());
+        assert!(ctx.buckets_path().join("extras/bucket").is_dir());
+        assert!(ctx.cache_path().join("sfsu#1.0.0#sfsu.zip").exists());
+        assert!(ctx.app_installed("sfsu").unwrap());
+        assert!(ctx.known_buckets().unwrap().contains_key("main"));
+        assert!(ctx.open_repo().is_none());
+    }
+}
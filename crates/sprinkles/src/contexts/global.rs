@@ -1,11 +1,12 @@
 use std::{
+    ffi::OsString,
     fs::File,
     path::{Path, PathBuf},
 };
 
 use crate::{config, git};
 
-use super::{ScoopContext, User};
+use super::{Error, ScoopContext, User};
 
 #[derive(Debug, Clone)]
 /// Global context adapter
@@ -23,26 +24,44 @@ impl Default for Global {
 impl Global {
     #[must_use]
     /// Construct a new global context adapter
+    ///
+    /// # Panics
+    /// - The Scoop config could not be loaded
+    /// - The user or global Scoop path does not exist
     pub fn new() -> Self {
-        use std::env::var_os;
-
-        let user_context = User::new();
-
-        let path = {
-            if let Some(path) = var_os("SCOOP_GLOBAL") {
-                path.into()
-            } else {
-                user_context.config().global_path.clone()
-            }
-        };
+        Self::try_new().unwrap_or_else(|e| panic!("{e}"))
+    }
 
-        let path = if path.exists() {
-            dunce::canonicalize(path).expect("failed to find real path to scoop")
+    /// Construct a new global context adapter
+    ///
+    /// The global Scoop path is read from the `SCOOP_GLOBAL` environment variable, or the config's `global_path`
+    ///
+    /// # Errors
+    /// - The user context could not be constructed
+    /// - The global Scoop path does not exist
+    pub fn try_new() -> Result<Self, Error> {
+        Self::try_new_with(&config::layers::Layers::discover()?, |var| {
+            std::env::var_os(var)
+        })
+    }
+
+    /// Construct a new global context adapter from the given config layers and environment variables
+    fn try_new_with(
+        layers: &config::layers::Layers,
+        env: impl Fn(&str) -> Option<OsString>,
+    ) -> Result<Self, Error> {
+        let user_context = User::try_new_with(layers, &env)?;
+
+        let path = if let Some(path) = env("SCOOP_GLOBAL") {
+            super::resolve_path(PathBuf::from(path), Error::MissingGlobalEnvPath)?
         } else {
-            panic!("Scoop path does not exist");
+            super::resolve_path(
+                user_context.config().global_path.clone(),
+                Error::MissingGlobalPath,
+            )?
         };
 
-        Self { path, user_context }
+        Ok(Self { path, user_context })
     }
 }
 
@@ -111,3 +130,51 @@ impl ScoopContext<config::Scoop> for Global {
         self.user_context.outdated().await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::config::layers::Layers;
+
+    use super::*;
+
+    #[test]
+    fn test_missing_user_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let layers = Layers {
+            user: Some(dir.path().join("config.json")),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            Global::try_new_with(&layers, |_| None),
+            Err(Error::MissingConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_missing_global_path() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("scoop")).unwrap();
+        let global_path = dir.path().join("global");
+
+        let config_path = dir.path().join("config.json");
+        std::fs::write(
+            &config_path,
+            serde_json::json!({
+                "root_path": dir.path().join("scoop"),
+                "global_path": global_path,
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let layers = Layers {
+            user: Some(config_path),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            Global::try_new_with(&layers, |_| None),
+            Err(Error::MissingGlobalPath(path)) if path == global_path
+        ));
+    }
+}
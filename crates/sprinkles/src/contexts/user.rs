@@ -1,4 +1,5 @@
 use std::{
+    ffi::OsString,
     fs::File,
     path::{Path, PathBuf},
 };
@@ -17,33 +18,42 @@ pub struct User {
 impl User {
     #[must_use]
     /// Construct a new user context adapter
+    ///
+    /// # Panics
+    /// - The Scoop config could not be loaded
+    /// - The Scoop path does not exist
     pub fn new() -> Self {
-        let path = {
-            if let Some(path) = crate::env::paths::scoop_path() {
-                path
-            } else if let Ok(path) = Ok::<_, ()>(
-                config::Scoop::load()
-                    .map(|config| config.root_path)
-                    .unwrap(),
-            ) {
-                path
-            } else {
-                directories::BaseDirs::new()
-                    .expect("user directories")
-                    .home_dir()
-                    .join("scoop")
-            }
-        };
+        Self::try_new().unwrap_or_else(|e| panic!("{e}"))
+    }
 
-        let path = if path.exists() {
-            dunce::canonicalize(path).expect("failed to find real path to scoop")
+    /// Construct a new user context adapter
+    ///
+    /// The config is resolved from every config layer.
+    /// The Scoop path is read from the `SCOOP` environment variable, or the config's `root_path`
+    ///
+    /// # Errors
+    /// - No Scoop config file exists, or one could not be loaded
+    /// - The Scoop path does not exist
+    pub fn try_new() -> Result<Self, Error> {
+        Self::try_new_with(&config::layers::Layers::discover()?, |var| {
+            std::env::var_os(var)
+        })
+    }
+
+    /// Construct a new user context adapter from the given config layers and environment variables
+    pub(super) fn try_new_with(
+        layers: &config::layers::Layers,
+        env: impl Fn(&str) -> Option<OsString>,
+    ) -> Result<Self, Error> {
+        let config = super::load_config(layers, &env)?;
+
+        let path = if let Some(path) = env("SCOOP") {
+            super::resolve_path(PathBuf::from(path), Error::MissingScoopEnvPath)?
         } else {
-            panic!("Scoop path does not exist");
+            super::resolve_path(config.root_path.clone(), Error::MissingRootPath)?
         };
 
-        let config = config::Scoop::load().expect("scoop config loaded correctly");
-
-        Self { config, path }
+        Ok(Self { config, path })
     }
 }
 
@@ -98,17 +108,13 @@ impl super::ScoopContext<config::Scoop> for User {
 
     #[must_use]
     /// Gets the user's scoop cache path
+    ///
+    /// The `SCOOP_CACHE` environment variable is already resolved into the config's `cache_path`
     fn cache_path(&self) -> PathBuf {
-        if let Some(cache_path) = crate::env::paths::scoop_cache() {
-            cache_path
-        } else if let Some(cache_path) = config::Scoop::load()
-            .ok()
-            .and_then(|config| config.cache_path)
-        {
-            cache_path
-        } else {
-            self.sub_path("cache")
-        }
+        self.config
+            .cache_path
+            .clone()
+            .unwrap_or_else(|| self.sub_path("cache"))
     }
 
     #[must_use]
@@ -252,3 +258,86 @@ impl super::ScoopContext<config::Scoop> for User {
         Ok(scoop_repo.outdated()?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::config::layers::Layers;
+
+    use super::*;
+
+    /// Layers with only a user config file, which is written if contents are given
+    fn user_layers(dir: &Path, contents: Option<serde_json::Value>) -> Layers {
+        let path = dir.join("config.json");
+
+        if let Some(contents) = contents {
+            std::fs::write(&path, contents.to_string()).unwrap();
+        }
+
+        Layers {
+            user: Some(path),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_missing_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let layers = user_layers(dir.path(), None);
+
+        assert!(matches!(
+            User::try_new_with(&layers, |_| None),
+            Err(Error::MissingConfig(path)) if path == dir.path().join("config.json")
+        ));
+    }
+
+    #[test]
+    fn test_missing_root_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let root_path = dir.path().join("scoop");
+        let layers = user_layers(
+            dir.path(),
+            Some(serde_json::json!({ "root_path": root_path })),
+        );
+
+        assert!(matches!(
+            User::try_new_with(&layers, |_| None),
+            Err(Error::MissingRootPath(path)) if path == root_path
+        ));
+    }
+
+    #[test]
+    fn test_missing_scoop_env_path() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("scoop")).unwrap();
+        let layers = user_layers(
+            dir.path(),
+            Some(serde_json::json!({ "root_path": dir.path().join("scoop") })),
+        );
+        let env_path = dir.path().join("missing");
+
+        assert!(matches!(
+            User::try_new_with(&layers, |var| (var == "SCOOP").then(|| env_path.clone().into())),
+            Err(Error::MissingScoopEnvPath(path)) if path == env_path
+        ));
+    }
+
+    #[test]
+    fn test_scoop_env_path_overrides_root_path() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("scoop")).unwrap();
+        let layers = user_layers(
+            dir.path(),
+            Some(serde_json::json!({ "root_path": dir.path().join("missing") })),
+        );
+
+        let ctx = User::try_new_with(&layers, |var| {
+            (var == "SCOOP").then(|| dir.path().join("scoop").into())
+        })
+        .unwrap();
+
+        assert_eq!(
+            ctx.path,
+            dunce::canonicalize(dir.path().join("scoop")).unwrap()
+        );
+    }
+}
@@ -1,7 +1,9 @@
 //! Scoop diagnostics helpers
 
+#[cfg(windows)]
 use std::{ffi::OsString, os::windows::ffi::OsStringExt};
 
+#[cfg(windows)]
 use itertools::Itertools;
 use serde::Serialize;
 
@@ -15,6 +17,7 @@ use crate::{
 #[allow(missing_docs)]
 /// Diagnostics errors
 pub enum Error {
+    #[cfg(windows)]
     #[error("Internal Windows API Error: {0}")]
     Windows(#[from] windows::core::Error),
     #[error("Interacting with buckets: {0}")]
@@ -34,6 +37,19 @@ pub enum LongPathsStatus {
     Disabled,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+/// A diagnostics check that relies on Windows APIs
+pub enum Check {
+    /// [`Diagnostics::long_paths`]
+    LongPaths,
+    /// [`Diagnostics::windows_developer`]
+    WindowsDeveloper,
+    /// [`Diagnostics::windows_defender`]
+    WindowsDefender,
+    /// [`Diagnostics::scoop_ntfs`]
+    ScoopNtfs,
+}
+
 #[derive(Debug, Copy, Clone, Serialize)]
 /// A helper program
 pub struct Helper {
@@ -47,6 +63,18 @@ pub struct Helper {
     pub packages: &'static [&'static str],
 }
 
+/// The checks that cannot be run on this platform
+const UNSUPPORTED_CHECKS: &[Check] = if cfg!(windows) {
+    &[]
+} else {
+    &[
+        Check::LongPaths,
+        Check::WindowsDeveloper,
+        Check::WindowsDefender,
+        Check::ScoopNtfs,
+    ]
+};
+
 const EXPECTED_HELPERS: &[Helper] = &[
     Helper {
         exe: "7z",
@@ -86,11 +114,17 @@ pub struct Diagnostics {
     pub missing_helpers: Vec<Helper>,
     /// If the Scoop directory is on an NTFS filesystem
     pub scoop_ntfs: bool,
+    /// The checks that cannot be run on this platform
+    ///
+    /// Their fields are always `false`, or [`LongPathsStatus::Disabled`] for long paths
+    pub unsupported: &'static [Check],
 }
 
 impl Diagnostics {
     /// Collect all diagnostics
     ///
+    /// Checks that rely on Windows APIs are reported as unsupported on other platforms
+    ///
     /// # Errors
     /// - Unable to check long paths
     /// - Unable to check main bucket
@@ -101,17 +135,27 @@ impl Diagnostics {
         debug!("Check git is installed");
         let main_bucket = Self::check_main_bucket(ctx)?;
         debug!("Checked main bucket");
-        let long_paths = Self::check_long_paths()?;
-        debug!("Checked long paths");
-        let windows_developer = Self::get_windows_developer_status()?;
-        debug!("Checked developer mode");
 
-        let windows_defender = if quork::root::is_root()? {
-            Self::check_windows_defender(ctx)?
-        } else {
-            false
-        };
-        debug!("Checked windows defender");
+        cfg_if::cfg_if! {
+            if #[cfg(windows)] {
+                let long_paths = Self::check_long_paths()?;
+                debug!("Checked long paths");
+                let windows_developer = Self::get_windows_developer_status()?;
+                debug!("Checked developer mode");
+
+                let windows_defender = if quork::root::is_root()? {
+                    Self::check_windows_defender(ctx)?
+                } else {
+                    false
+                };
+                debug!("Checked windows defender");
+            } else {
+                let long_paths = LongPathsStatus::Disabled;
+                let windows_developer = false;
+                let windows_defender = false;
+                debug!("Skipped Windows specific checks");
+            }
+        }
 
         let missing_helpers = EXPECTED_HELPERS
             .iter()
@@ -119,7 +163,10 @@ impl Diagnostics {
             .copied()
             .collect();
 
+        #[cfg(windows)]
         let scoop_ntfs = Self::is_ntfs(ctx)?;
+        #[cfg(not(windows))]
+        let scoop_ntfs = false;
 
         Ok(Self {
             git_installed,
@@ -129,9 +176,17 @@ impl Diagnostics {
             windows_defender,
             missing_helpers,
             scoop_ntfs,
+            unsupported: UNSUPPORTED_CHECKS,
         })
     }
 
+    #[must_use]
+    /// Check if the check could be run on this platform
+    pub fn is_supported(&self, check: Check) -> bool {
+        !self.unsupported.contains(&check)
+    }
+
+    #[cfg(windows)]
     #[allow(unreachable_code)]
     /// Check if Windows Defender is ignoring the Scoop directory
     ///
@@ -161,6 +216,7 @@ impl Diagnostics {
         Ok(buckets.into_iter().any(|bucket| bucket.name() == "main"))
     }
 
+    #[cfg(windows)]
     /// Check if long paths are enabled
     ///
     /// # Errors
@@ -189,6 +245,7 @@ impl Diagnostics {
         }
     }
 
+    #[cfg(windows)]
     /// Check if the user has developer mode enabled
     ///
     /// # Errors
@@ -203,6 +260,7 @@ impl Diagnostics {
         Ok(key.get_value::<u32, _>("AllowDevelopmentWithoutDevLicense")? == 1)
     }
 
+    #[cfg(windows)]
     /// Check if the Scoop directory is on an NTFS filesystem
     ///
     /// # Errors
@@ -255,3 +313,56 @@ impl Diagnostics {
         which::which("git").is_ok()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::contexts::Fixture;
+
+    use super::*;
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_windows_checks_unsupported() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = Fixture::builder(dir.path()).bucket("main").build().unwrap();
+
+        let diagnostics = Diagnostics::collect(&ctx).unwrap();
+
+        assert!(diagnostics.main_bucket);
+        for check in [
+            Check::LongPaths,
+            Check::WindowsDeveloper,
+            Check::WindowsDefender,
+            Check::ScoopNtfs,
+        ] {
+            assert!(!diagnostics.is_supported(check));
+        }
+        assert!(!diagnostics.windows_developer);
+        assert!(!diagnostics.windows_defender);
+        assert!(!diagnostics.scoop_ntfs);
+    }
+
+    #[test]
+    fn test_serialize_unsupported() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = Fixture::builder(dir.path()).build().unwrap();
+
+        let diagnostics = Diagnostics {
+            git_installed: true,
+            long_paths: LongPathsStatus::Disabled,
+            main_bucket: Diagnostics::check_main_bucket(&ctx).unwrap(),
+            windows_developer: false,
+            windows_defender: false,
+            missing_helpers: vec![],
+            scoop_ntfs: false,
+            unsupported: &[Check::LongPaths],
+        };
+
+        assert!(!diagnostics.is_supported(Check::LongPaths));
+        assert!(diagnostics.is_supported(Check::ScoopNtfs));
+        assert_eq!(
+            serde_json::to_value(&diagnostics).unwrap()["unsupported"],
+            serde_json::json!(["LongPaths"])
+        );
+    }
+}
@@ -24,6 +24,17 @@ pub mod paths {
         env::var_os("SCOOP_CACHE").map(PathBuf::from)
     }
 
+    /// Get the machine-wide config directory
+    pub fn system_config_dir() -> Option<PathBuf> {
+        cfg_if::cfg_if! {
+            if #[cfg(windows)] {
+                Some(env::var_os("ProgramData").map_or_else(|| PathBuf::from("C:\\ProgramData"), PathBuf::from))
+            } else {
+                Some(PathBuf::from("/etc"))
+            }
+        }
+    }
+
     pub fn config_dir() -> Option<PathBuf> {
         env::var_os("XFG_CONFIG_HOME")
             .map(PathBuf::from)
@@ -7,7 +7,7 @@ use git2::{Commit, DiffOptions, Direction, FetchOptions, Oid, Progress, Remote,
 use gix::traverse::commit::simple::Sorting;
 use indicatif::ProgressBar;
 
-use crate::{buckets::Bucket, contexts::ScoopContext};
+use crate::{buckets::Bucket, config, contexts::ScoopContext, offline, proxy::Proxy};
 
 use self::pull::ProgressCallback;
 
@@ -68,6 +68,8 @@ pub enum Error {
     MissingHead,
     #[error("Invalid utf8")]
     NonUtf8,
+    #[error("Cannot connect to the remote repository while offline")]
+    Offline,
 }
 
 /// Repo result type
@@ -75,7 +77,7 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 #[derive(Deref)]
 /// A git repository
-pub struct Repo(Repository);
+pub struct Repo(#[deref] Repository, Proxy);
 
 impl Repo {
     /// Convert into a gitoxide repository
@@ -98,23 +100,33 @@ impl Repo {
 
     /// Open the repository from the bucket path
     ///
+    /// Remote operations use the bucket's proxy
+    ///
     /// # Errors
     /// - The bucket could not be opened as a repository
     pub fn from_bucket(bucket: &Bucket) -> Result<Self> {
         let repo = Repository::open(bucket.path())?;
 
-        Ok(Self(repo))
+        Ok(Self(repo, bucket.proxy().clone()))
     }
 
     /// Open Scoop app repository
     ///
+    /// Remote operations use the proxy from the context's config
+    ///
     /// # Errors
     /// - The Scoop app could not be opened as a repository
-    pub fn scoop_app<C>(context: &impl ScoopContext<C>) -> Result<Self> {
+    pub fn scoop_app(context: &impl ScoopContext<config::Scoop>) -> Result<Self> {
         let scoop_path = context.apps_path().join("scoop").join("current");
         let repo = Repository::open(scoop_path)?;
 
-        Ok(Self(repo))
+        Ok(Self(repo, Proxy::from_config(context.config())))
+    }
+
+    #[must_use]
+    /// Get the proxy used for remote operations
+    pub fn proxy(&self) -> &Proxy {
+        &self.1
     }
 
     #[must_use]
@@ -162,11 +174,16 @@ impl Repo {
     /// - No remote named "origin"
     /// - No active branch
     pub fn fetch(&self) -> Result<()> {
+        if offline::enabled() {
+            return Err(Error::Offline);
+        }
+
         let current_branch = self.current_branch()?;
 
         // Fetch the latest changes from the remote repository
         let mut fetch_options = FetchOptions::new();
         fetch_options.update_fetchhead(true);
+        fetch_options.proxy_options(self.1.git2_options());
         let mut remote = self.0.find_remote("origin")?;
         remote.fetch(&[current_branch], Some(&mut fetch_options), None)?;
 
@@ -179,11 +196,16 @@ impl Repo {
     /// - No remote named "origin"
     /// - Missing head
     pub fn latest_remote_commit(&self) -> Result<Oid> {
+        if offline::enabled() {
+            return Err(Error::Offline);
+        }
+
         let mut remote = self
             .origin()
             .ok_or(Error::MissingRemote("origin".to_string()))?;
 
-        let connection = remote.connect_auth(Direction::Fetch, None, None)?;
+        let connection =
+            remote.connect_auth(Direction::Fetch, None, Some(self.1.git2_options()))?;
 
         let current_branch = self.current_branch()?;
         let head = connection
@@ -271,6 +293,10 @@ impl Repo {
     /// - Missing latest commit
     /// - Git error
     pub fn pull(&self, stats_cb: Option<ProgressCallback<'_>>) -> Result<()> {
+        if offline::enabled() {
+            return Err(Error::Offline);
+        }
+
         let current_branch = self.current_branch()?;
 
         pull::pull(self, None, Some(current_branch.as_str()), stats_cb)?;
@@ -291,6 +317,10 @@ impl Repo {
         &self,
         stats_cb: Option<ProgressCallback<'_>>,
     ) -> Result<Vec<String>> {
+        if offline::enabled() {
+            return Err(Error::Offline);
+        }
+
         let repo = self.to_gitoxide()?;
 
         let current_commit = repo.head_commit()?;
@@ -29,6 +29,8 @@ use gix::{
     Repository,
 };
 
+use crate::{config, offline, proxy::Proxy};
+
 pub use gix::progress;
 
 #[derive(Debug, thiserror::Error)]
@@ -43,20 +45,31 @@ pub enum Error {
     Checkout(#[from] gix::clone::checkout::main_worktree::Error),
     #[error("No pack received from remote")]
     NoPackReceived,
+    #[error("Cannot clone repositories while offline")]
+    Offline,
 }
 
 /// Clone result
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
-/// Clone a git repository
+/// Clone a git repository, using the proxy configured in the given Scoop config
 ///
 /// # Errors
 /// - Git error
-pub fn clone<P>(url: &str, path: impl AsRef<Path>, pb: P) -> Result<Repository>
+pub fn clone<P>(
+    url: &str,
+    path: impl AsRef<Path>,
+    pb: P,
+    config: &config::Scoop,
+) -> Result<Repository>
 where
     P: gix::NestedProgress,
     P::SubProgress: 'static,
 {
+    if offline::enabled() {
+        return Err(Error::Offline);
+    }
+
     let interrupt = AtomicBool::new(false);
 
     // Fetch the latest changes from the remote repository
@@ -65,7 +78,7 @@ where
         path,
         create::Kind::WithWorktree,
         CreateOptions::default(),
-        OpenOptions::default(),
+        OpenOptions::default().config_overrides(Proxy::from_config(config).gix_overrides()),
     )?;
 
     let (mut checkout, outcome) = fetch.fetch_then_checkout(pb, &interrupt)?;
@@ -22,12 +22,15 @@ use std::str;
 
 use git2::Repository;
 
+use crate::proxy::Proxy;
+
 pub type ProgressCallback<'a> = &'a dyn Fn(git2::Progress<'_>, bool) -> bool;
 
 fn do_fetch<'a>(
     repo: &'a git2::Repository,
     refs: &[&str],
     remote: &'a mut git2::Remote<'_>,
+    proxy: &Proxy,
     stats_cb: Option<ProgressCallback<'_>>,
 ) -> Result<git2::AnnotatedCommit<'a>, git2::Error> {
     let mut cb = git2::RemoteCallbacks::new();
@@ -39,6 +42,7 @@ fn do_fetch<'a>(
 
     let mut fo = git2::FetchOptions::new();
     fo.remote_callbacks(cb);
+    fo.proxy_options(proxy.git2_options());
     // Always fetch all tags.
     // Perform a download and also update tips
     fo.download_tags(git2::AutotagOption::All);
@@ -171,7 +175,7 @@ pub fn pull(
     let remote_name = remote.unwrap_or("origin");
     let remote_branch = branch.unwrap_or("master");
     let mut remote = repo.find_remote(remote_name)?;
-    do_fetch(repo, &[remote_branch], &mut remote, stats_cb)?;
+    do_fetch(repo, &[remote_branch], &mut remote, repo.proxy(), stats_cb)?;
     let fetch_commit = repo.find_commit(repo.latest_remote_commit()?)?;
     Ok(do_merge(repo, remote_branch, &fetch_commit)?)
 }
@@ -1,11 +1,11 @@
 //! Manifest hashing utilities
 
-use std::{fmt::Display, io::BufRead, num::ParseIntError, str::FromStr};
+use std::{collections::HashMap, fmt::Display, io::BufRead, num::ParseIntError, str::FromStr};
 
 use formats::{json, text};
 use regex::Regex;
 use reqwest::{
-    header::{HeaderMap, HeaderValue},
+    header::{self, HeaderMap, HeaderValue},
     StatusCode,
 };
 use substitutions::SubstitutionMap;
@@ -16,6 +16,7 @@ use crate::{
     config,
     contexts::ScoopContext,
     hash::url_ext::UrlExt,
+    offline,
     packages::{
         models::manifest::{
             AutoupdateConfig, HashExtractionOrArrayOfHashExtractions, HashMode as ManifestHashMode,
@@ -23,7 +24,7 @@ use crate::{
         },
         Manifest, MergeDefaults,
     },
-    requests::{AsyncClient, Client},
+    requests::AsyncClient,
     version::Version,
     Architecture,
 };
@@ -69,6 +70,8 @@ pub enum Error {
     RDFError(#[from] formats::rdf::RDFError),
     #[error("XML error: {0}")]
     XMLError(#[from] formats::xml::XMLError),
+    #[error("Headers error: {0}")]
+    HeadersError(#[from] formats::headers::Error),
     #[error("Error parsing json: {0}")]
     SerdeJson(#[from] serde_json::Error),
     #[error("Failed to parse url: {0}")]
@@ -107,6 +110,8 @@ pub enum Error {
     DecodingHexUtf8(#[from] std::str::Utf8Error),
     #[error("Interacting with cache failed: {0}")]
     CacheError(#[from] cache::Error),
+    #[error("Cannot fetch hashes while offline")]
+    Offline,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -134,6 +139,12 @@ impl Hash {
     pub fn hash_type(&self) -> HashType {
         self.hash_type
     }
+
+    #[must_use]
+    /// Check if two hashes are the same, ignoring the case of the hex string
+    pub fn matches(&self, other: &Hash) -> bool {
+        self.hash_type == other.hash_type && self.hash.eq_ignore_ascii_case(&other.hash)
+    }
 }
 
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
@@ -235,6 +246,8 @@ pub enum HashMode {
     Rdf,
     /// Extract the hash from the Sourceforge page
     Sourceforge,
+    /// Extract the hash from the response headers of a `HEAD` request
+    Headers,
 }
 
 impl HashMode {
@@ -297,6 +310,7 @@ impl HashMode {
                         ManifestHashMode::Sourceforge => Some(HashMode::Sourceforge),
                         ManifestHashMode::Metalink => Some(HashMode::Metalink),
                         ManifestHashMode::Rdf => Some(HashMode::Rdf),
+                        ManifestHashMode::Headers => Some(HashMode::Headers),
                         _ => None,
                     })
                     .or_else(|| {
@@ -362,6 +376,18 @@ impl Hash {
         ctx: &impl ScoopContext<config::Scoop>,
         manifest: &Manifest,
         arch: Architecture,
+    ) -> Result<Vec<Hash>, Error> {
+        Self::get_for_app_with_matches(ctx, manifest, arch, &HashMap::new()).await
+    }
+
+    /// Get the hashes for the app, substituting the checkver captures (i.e `$matchBuild`) into the hash extraction config
+    ///
+    /// See [`Hash::get_for_app`]
+    pub(crate) async fn get_for_app_with_matches(
+        ctx: &impl ScoopContext<config::Scoop>,
+        manifest: &Manifest,
+        arch: Architecture,
+        matches: &HashMap<String, String>,
     ) -> Result<Vec<Hash>, Error> {
         let autoupdate_config = manifest
             .autoupdate_config(arch)
@@ -369,22 +395,20 @@ impl Hash {
 
         let hash_mode = HashMode::from_manifest(manifest, arch).unwrap_or_default();
 
-        if hash_mode == HashMode::Download {
-            let cache_handles = Handle::open_manifest(ctx.cache_path(), manifest, arch)?;
+        // Downloaded hashes may still be computed from files that are already cached
+        if hash_mode != HashMode::Download && offline::enabled() {
+            return Err(Error::Offline);
+        }
 
-            let downloaders = cache_handles
-                .into_iter()
-                .map(|handle| async move { Downloader::new::<AsyncClient>(handle, None).await });
-            let downloaders = futures::future::try_join_all(downloaders).await?;
+        if hash_mode == HashMode::Download {
+            // The manifest's hashes are for the previous version, so the handles are opened from the urls only
+            let cache_handles = Handle::open_manifest_urls(ctx.cache_path(), manifest, arch)?;
 
-            let hashes = downloaders.into_iter().map(Downloader::download);
-            let hashes = futures::future::try_join_all(hashes)
-                .await?
+            let cache_handles = cache_handles
                 .into_iter()
-                .map(|hash| hash.computed_hash)
-                .collect();
+                .map(|handle| handle.with_config(ctx.config()));
 
-            return Ok(hashes);
+            return Self::download_all(cache_handles).await;
 
             // return if let Some(dl_url) = manifest
             //     .architecture
@@ -408,11 +432,17 @@ impl Hash {
             .map(|urls| Url::parse(&urls).map_err(Error::InvalidUrl))
             .collect::<Result<Vec<_>, _>>()?;
 
+        let client = AsyncClient::with_config(ctx.config());
+        let cookie = manifest.cookie_header();
+
         let hashes = manifest_urls.into_iter().map(|manifest_url| {
             Self::get_for_url(
+                &client,
+                cookie.as_deref(),
                 hash_mode.clone(),
                 manifest_url,
                 &manifest.version,
+                matches,
                 &autoupdate_config,
             )
         });
@@ -420,13 +450,61 @@ impl Hash {
         futures::future::try_join_all(hashes).await
     }
 
+    /// Download each handle, and get the hashes of the downloaded files
+    async fn download_all(handles: impl IntoIterator<Item = Handle>) -> Result<Vec<Hash>, Error> {
+        // Creating a downloader waits for the download limits,
+        // so each download must finish before its permit can be used by another handle
+        let hashes = handles.into_iter().map(|handle| async move {
+            let result = Downloader::new::<AsyncClient>(handle, None)
+                .await?
+                .download()
+                .await?;
+
+            Ok::<_, Error>(result.computed_hash)
+        });
+
+        futures::future::try_join_all(hashes).await
+    }
+
     async fn get_for_url(
+        client: &AsyncClient,
+        cookie: Option<&str>,
         mut hash_mode: HashMode,
         manifest_url: Url,
         version: &Version,
+        matches: &HashMap<String, String>,
         autoupdate_config: &AutoupdateConfig,
     ) -> Result<Hash, Error> {
-        let submap = SubstitutionMap::from_all(version, &manifest_url);
+        if offline::enabled() {
+            return Err(Error::Offline);
+        }
+
+        let mut submap = SubstitutionMap::from_all(version, &manifest_url);
+        submap.append_matches(matches);
+
+        if hash_mode == HashMode::Headers {
+            // Default to the download url itself, as that is where the headers are usually published
+            let url = match autoupdate_config
+                .hash
+                .as_ref()
+                .and_then(|hash| hash.as_object())
+                .and_then(|hash_extraction| hash_extraction.url.clone())
+            {
+                Some(url) => Url::parse(&url.into_substituted(&submap, false))?,
+                None => {
+                    let mut url = manifest_url;
+                    url.strip_fragment();
+                    url
+                }
+            };
+
+            let mut request = client.head(url.as_str());
+            if let Some(cookie) = cookie {
+                request = request.header(header::COOKIE, cookie);
+            }
+
+            return Hash::from_head_response(request.send().await?);
+        }
 
         let url = if matches!(hash_mode, HashMode::Fosshub | HashMode::Sourceforge) {
             let (url, regex): (Url, String) = match hash_mode {
@@ -498,7 +576,15 @@ impl Hash {
                 .and_then(|url: String| Ok(Url::parse(&url)?))?
         };
 
-        let source = Client::asynchronous().get(url.as_str()).send().await?;
+        let mut request = client.get(url.as_str());
+        if let Some(cookie) = cookie {
+            request = request.header(header::COOKIE, cookie);
+        }
+
+        let source = request.send().await?;
+        if !source.status().is_success() {
+            return Err(Error::ErrorStatus(source.status()));
+        }
 
         if hash_mode == HashMode::HashUrl {
             let hash = source.text().await?;
@@ -623,10 +709,36 @@ impl Hash {
 
     /// Find a hash in the headers of a response
     ///
+    /// The strongest hash found is used. Supported headers are `Digest`, `Content-Digest`, `Repr-Digest`,
+    /// `x-amz-checksum-sha256`, `x-amz-checksum-sha1`, `x-goog-hash`, `Content-MD5` and `ETag`
+    ///
+    /// # Errors
+    /// - If the hash is not found
+    /// - If the hash is invalid
+    pub fn find_hash_in_headers(headers: &HeaderMap<HeaderValue>) -> Result<Hash, Error> {
+        let hash = formats::headers::parse_headers(headers)?;
+        let hash_type = HashType::try_from(&hash)?;
+
+        Ok(Hash { hash, hash_type })
+    }
+
+    /// Send a `HEAD` request to the url, and find a hash in the response headers
+    ///
     /// # Errors
-    /// peepeepoopoo
-    pub fn find_hash_in_headers(_headers: &HeaderMap<HeaderValue>) -> Result<Hash, Error> {
-        unimplemented!("I can't find a location where this is ever used")
+    /// - If the request fails
+    /// - If the server responds with an error status
+    /// - If the hash is not found
+    pub async fn from_head_request(client: &reqwest::Client, url: Url) -> Result<Hash, Error> {
+        Self::from_head_response(client.head(url).send().await?)
+    }
+
+    /// Find a hash in the headers of a response to a `HEAD` request
+    fn from_head_response(response: reqwest::Response) -> Result<Hash, Error> {
+        if !response.status().is_success() {
+            return Err(Error::ErrorStatus(response.status()));
+        }
+
+        Hash::find_hash_in_headers(response.headers())
     }
 }
 
@@ -646,6 +758,131 @@ mod tests {
 
     use super::*;
 
+    #[tokio::test]
+    async fn test_hash_from_head_request() -> anyhow::Result<()> {
+        use crate::test_server::{Response, TestServer};
+
+        let server = TestServer::start(|request| {
+            if request.path == "/app.zip" {
+                Response::ok("app contents").with_header(
+                    "x-amz-checksum-sha256",
+                    "uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=",
+                )
+            } else {
+                Response::status(404)
+            }
+        });
+
+        let client = reqwest::Client::new();
+
+        let hash = Hash::from_head_request(&client, Url::parse(&server.url("/app.zip"))?).await?;
+        assert_eq!(
+            hash.to_string(),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+
+        let missing = Hash::from_head_request(&client, Url::parse(&server.url("/missing"))?).await;
+        assert!(matches!(
+            missing,
+            Err(Error::ErrorStatus(StatusCode::NOT_FOUND))
+        ));
+
+        // Only the headers are needed, so the download itself should never be requested
+        assert!(server
+            .requests()
+            .iter()
+            .all(|request| request.method == "HEAD"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_download_all_with_concurrency_limit() -> anyhow::Result<()> {
+        use digest::Digest;
+
+        use crate::{
+            cache::limits::DownloadLimits,
+            packages::CreateManifest,
+            test_server::{Response, TestServer},
+        };
+
+        let server = TestServer::start(|request| Response::ok(request.path.clone()));
+        let dir = tempfile::tempdir()?;
+
+        let manifest = Manifest::from_str(format!(
+            r#"{{"version":"1.0.0","url":["{}","{}"]}}"#,
+            server.url("/first.zip"),
+            server.url("/second.zip"),
+        ))?;
+
+        // Only one download may run at once, so the second must wait for the first to finish
+        let limits = DownloadLimits::new(Some(1), None);
+        let handles = Handle::open_manifest_urls(dir.path(), &manifest, Architecture::ARCH)?
+            .into_iter()
+            .map(|handle| handle.with_limits(limits.clone()));
+
+        let hashes = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            Hash::download_all(handles),
+        )
+        .await
+        .expect("downloads should not deadlock")?;
+
+        assert_eq!(
+            hashes,
+            [
+                Hash::from_hex(&sha2::Sha256::digest(b"/first.zip")),
+                Hash::from_hex(&sha2::Sha256::digest(b"/second.zip")),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hash_url_sends_manifest_cookie() -> anyhow::Result<()> {
+        use crate::test_server::{Response, TestServer};
+
+        const COOKIE: &str = "oraclelicense=accept-securebackup-cookie";
+        const HASH: &str = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+
+        let server = TestServer::start(|request| {
+            if request.header("cookie") == Some(COOKIE) {
+                Response::ok(HASH)
+            } else {
+                Response::status(403)
+            }
+        });
+
+        let autoupdate_config: AutoupdateConfig = serde_json::from_value(serde_json::json!({
+            "hash": { "url": server.url("/app.zip.sha256") }
+        }))?;
+        let client = AsyncClient::from(reqwest::Client::new());
+        let version = Version::new("1.0.0");
+        let get_hash = |cookie| {
+            Hash::get_for_url(
+                &client,
+                cookie,
+                HashMode::HashUrl,
+                Url::parse(&server.url("/app.zip")).unwrap(),
+                &version,
+                &HashMap::new(),
+                &autoupdate_config,
+            )
+        };
+
+        let hash = get_hash(Some(COOKIE)).await?;
+        assert_eq!(hash.to_string(), HASH);
+
+        let rejected = get_hash(None).await;
+        assert!(matches!(
+            rejected,
+            Err(Error::ErrorStatus(StatusCode::FORBIDDEN))
+        ));
+
+        Ok(())
+    }
+
     #[test]
     fn test_compute_hashes() {
         let data = b"hello world";
@@ -784,6 +1021,10 @@ mod tests {
     }
 
     #[tokio::test]
+    #[cfg_attr(
+        not(windows),
+        ignore = "Requires a Scoop install with the extras bucket, and network access"
+    )]
     async fn test_springboot() -> anyhow::Result<()> {
         let package = reference::Package::from_str("extras/springboot")?;
         let handler = TestHandler::new(package);
@@ -792,6 +1033,10 @@ mod tests {
     }
 
     #[tokio::test]
+    #[cfg_attr(
+        not(windows),
+        ignore = "Requires a Scoop install with the extras bucket, and network access"
+    )]
     async fn test_sfsu() -> anyhow::Result<()> {
         let package = reference::Package::from_str("extras/sfsu")?;
 
@@ -803,6 +1048,10 @@ mod tests {
     }
 
     #[tokio::test]
+    #[cfg_attr(
+        not(windows),
+        ignore = "Requires a Scoop install with the extras bucket, and network access"
+    )]
     async fn test_finding_vcredistaio_hashes() -> anyhow::Result<()> {
         let package = reference::Package::from_str("extras/vcredist-aio")?;
 
@@ -822,6 +1071,10 @@ mod tests {
     // }
 
     #[tokio::test]
+    #[cfg_attr(
+        not(windows),
+        ignore = "Requires a Scoop install with the extras bucket, and network access"
+    )]
     async fn test_keepass() -> anyhow::Result<()> {
         let package = reference::Package::from_str("extras/keepass")?;
 
@@ -833,6 +1086,10 @@ mod tests {
     }
 
     #[tokio::test]
+    #[cfg_attr(
+        not(windows),
+        ignore = "Requires a Scoop install with the extras bucket, and network access"
+    )]
     async fn test_hwinfo() -> anyhow::Result<()> {
         let package = reference::Package::from_str("extras/hwinfo")?;
 
@@ -853,12 +1110,20 @@ mod tests {
     }
 
     #[tokio::test]
+    #[cfg_attr(
+        not(windows),
+        ignore = "Requires a Scoop install with the extras bucket, and network access"
+    )]
     async fn test_firefox() -> anyhow::Result<()> {
         let package = reference::Package::from_str("extras/firefox")?;
         TestHandler::new(package).test().await
     }
 
     #[tokio::test]
+    #[cfg_attr(
+        not(windows),
+        ignore = "Requires a Scoop install with the extras bucket, and network access"
+    )]
     async fn test_sfsu_autoupdate() -> anyhow::Result<()> {
         let mut package = reference::Package::from_str("extras/sfsu")?;
         package.set_version("1.10.2".to_string());
@@ -882,6 +1147,10 @@ mod tests {
     }
 
     #[tokio::test]
+    #[cfg_attr(
+        not(windows),
+        ignore = "Requires a Scoop install with the extras bucket, and network access"
+    )]
     async fn test_computed_hash() -> anyhow::Result<()> {
         let ctx = User::new();
 
@@ -1,3 +1,4 @@
+pub mod headers;
 pub mod json;
 pub mod rdf;
 pub mod text;
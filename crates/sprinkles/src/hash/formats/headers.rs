@@ -0,0 +1,179 @@
+use base64::prelude::*;
+use reqwest::header::HeaderMap;
+
+use crate::hash::encode_hex;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Hash could not be found in the response headers")]
+    NotFound,
+}
+
+/// Find the strongest hash published in the response headers, as a lowercase hex string
+///
+/// Supported headers are `Digest`, `Content-Digest` and `Repr-Digest`,
+/// `x-amz-checksum-sha256` and `x-amz-checksum-sha1`, `x-goog-hash`,
+/// `Content-MD5`, and `ETag` (only if it is a plain hex hash)
+pub fn parse_headers(headers: &HeaderMap) -> Result<String, Error> {
+    let values = |name: &'static str| {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+    };
+
+    // Ordered from most to least trustworthy, for hashes of the same length
+    let mut candidates = vec![];
+
+    for name in ["digest", "content-digest", "repr-digest"] {
+        candidates.extend(values(name).flat_map(parse_digest));
+    }
+
+    for (name, length) in [("x-amz-checksum-sha256", 32), ("x-amz-checksum-sha1", 20)] {
+        candidates.extend(values(name).filter_map(|value| decode_base64(value, length)));
+    }
+
+    candidates.extend(values("x-goog-hash").flat_map(|value| {
+        value
+            .split(',')
+            .filter_map(|item| item.trim().strip_prefix("md5="))
+            .filter_map(|md5| decode_base64(md5, 16))
+            .collect::<Vec<_>>()
+    }));
+
+    candidates.extend(values("content-md5").filter_map(|value| decode_base64(value, 16)));
+
+    candidates.extend(values("etag").filter_map(parse_etag));
+
+    // Prefer the strongest algorithm, and otherwise the first header it was found in
+    candidates
+        .into_iter()
+        .rev()
+        .max_by_key(Vec::len)
+        .map(|hash| encode_hex(&hash))
+        .ok_or(Error::NotFound)
+}
+
+/// Parse the RFC 3230 `Digest` header, or the RFC 9530 `Content-Digest` and `Repr-Digest` headers
+fn parse_digest(value: &str) -> Vec<Vec<u8>> {
+    value
+        .split(',')
+        .filter_map(|item| {
+            let (algorithm, digest) = item.trim().split_once('=')?;
+
+            let length = match algorithm.to_lowercase().as_str() {
+                "md5" => 16,
+                "sha" | "sha-1" => 20,
+                "sha-256" => 32,
+                "sha-512" => 64,
+                _ => return None,
+            };
+
+            // RFC 9530 wraps the digest in colons
+            decode_base64(digest.trim().trim_matches(':'), length)
+        })
+        .collect()
+}
+
+fn parse_etag(value: &str) -> Option<Vec<u8>> {
+    // Weak validators are not derived from the content
+    if value.starts_with("W/") {
+        return None;
+    }
+
+    let etag = value.trim_matches('"');
+
+    if ![32, 40, 64, 128].contains(&etag.len()) {
+        return None;
+    }
+
+    crate::hash::decode_hex(etag).ok()
+}
+
+fn decode_base64(value: &str, length: usize) -> Option<Vec<u8>> {
+    BASE64_STANDARD
+        .decode(value.trim())
+        .ok()
+        .filter(|decoded| decoded.len() == length)
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    const SHA256: &str = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+    const MD5: &str = "5eb63bbbe01eeed093cb22bb8f5acdc3";
+
+    fn headers(headers: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+
+        for (name, value) in headers {
+            map.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+
+        map
+    }
+
+    fn base64(hex: &str) -> String {
+        BASE64_STANDARD.encode(crate::hash::decode_hex(hex).unwrap())
+    }
+
+    #[test]
+    fn test_digest() {
+        let digest = format!("MD5={}, SHA-256={}", base64(MD5), base64(SHA256));
+
+        assert_eq!(
+            parse_headers(&headers(&[("digest", &digest)])).unwrap(),
+            SHA256
+        );
+    }
+
+    #[test]
+    fn test_content_digest() {
+        let digest = format!("sha-256=:{}:", base64(SHA256));
+
+        assert_eq!(
+            parse_headers(&headers(&[("content-digest", &digest)])).unwrap(),
+            SHA256
+        );
+    }
+
+    #[test]
+    fn test_cloud_storage() {
+        assert_eq!(
+            parse_headers(&headers(&[("x-amz-checksum-sha256", &base64(SHA256))])).unwrap(),
+            SHA256
+        );
+
+        let goog_hash = headers(&[
+            ("x-goog-hash", "crc32c=n03x6A=="),
+            ("x-goog-hash", &format!("md5={}", base64(MD5))),
+        ]);
+        assert_eq!(parse_headers(&goog_hash).unwrap(), MD5);
+    }
+
+    #[test]
+    fn test_strongest_hash_wins() {
+        let headers = headers(&[
+            ("content-md5", &base64(MD5)),
+            ("x-amz-checksum-sha256", &base64(SHA256)),
+        ]);
+
+        assert_eq!(parse_headers(&headers).unwrap(), SHA256);
+    }
+
+    #[test]
+    fn test_etag() {
+        assert_eq!(
+            parse_headers(&headers(&[("etag", &format!("\"{MD5}\""))])).unwrap(),
+            MD5
+        );
+
+        // Multipart uploads and weak validators are not content hashes
+        for etag in [format!("\"{MD5}-2\""), format!("W/\"{MD5}\"")] {
+            assert!(parse_headers(&headers(&[("etag", &etag)])).is_err());
+        }
+    }
+}
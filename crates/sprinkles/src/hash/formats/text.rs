@@ -55,6 +55,11 @@ impl RegexTemplates {
     }
 }
 
+/// Check if the variable is a hash template, like `$sha256`, which can be used in hash extraction regexes
+pub fn is_template(variable: &str) -> bool {
+    RegexTemplates::into_substitute_map().contains_key(variable)
+}
+
 pub fn parse_text(
     source: impl AsRef<str>,
     substitutions: &SubstitutionMap,
@@ -5,7 +5,9 @@ use url::Url;
 
 use crate::{
     hash::url_ext::UrlExt,
-    packages::models::manifest::{AliasArray, Installer, StringArray, TOrArrayOfTs},
+    packages::models::manifest::{
+        AliasArray, Installer, License, PackageLicense, Psmodule, StringArray, TOrArrayOfTs,
+    },
     version::Version,
 };
 
@@ -13,6 +15,27 @@ use crate::{
 pub struct SubstitutionMap(HashMap<String, String>);
 
 impl SubstitutionMap {
+    /// Every variable that can be substituted from a version or url
+    pub const VARIABLES: &'static [&'static str] = &[
+        "$version",
+        "$dotVersion",
+        "$underscoreVersion",
+        "$dashVersion",
+        "$cleanVersion",
+        "$majorVersion",
+        "$minorVersion",
+        "$patchVersion",
+        "$buildVersion",
+        "$preReleaseVersion",
+        "$matchHead",
+        "$matchTail",
+        "$url",
+        "$baseurl",
+        "$basename",
+        "$basenameNoExt",
+        "$urlNoExt",
+    ];
+
     pub fn new() -> Self {
         Self(HashMap::new())
     }
@@ -38,6 +61,11 @@ impl SubstitutionMap {
     pub fn append_url(&mut self, url: &Url) {
         self.extend(url.submap().0);
     }
+
+    /// Append the checkver regex captures to the map, i.e `$match1` and `$matchBuild`
+    pub fn append_matches(&mut self, matches: &HashMap<String, String>) {
+        self.extend(matches.clone());
+    }
 }
 
 impl Default for SubstitutionMap {
@@ -151,3 +179,58 @@ impl Substitute for Installer {
         }
     }
 }
+
+impl Substitute for serde_json::Value {
+    fn into_substituted(self, params: &SubstitutionMap, regex_escape: bool) -> Self {
+        match self {
+            serde_json::Value::String(s) => {
+                serde_json::Value::String(s.into_substituted(params, regex_escape))
+            }
+            serde_json::Value::Array(values) => serde_json::Value::Array(
+                values
+                    .into_iter()
+                    .map(|value| value.into_substituted(params, regex_escape))
+                    .collect(),
+            ),
+            value => value,
+        }
+    }
+}
+
+impl Substitute for HashMap<String, Option<serde_json::Value>> {
+    fn into_substituted(self, params: &SubstitutionMap, regex_escape: bool) -> Self {
+        self.into_iter()
+            .map(|(key, value)| {
+                (
+                    key,
+                    value.map(|value| value.into_substituted(params, regex_escape)),
+                )
+            })
+            .collect()
+    }
+}
+
+impl Substitute for PackageLicense {
+    fn into_substituted(self, params: &SubstitutionMap, regex_escape: bool) -> Self {
+        match self {
+            PackageLicense::License(License { identifier, url }) => {
+                PackageLicense::License(License {
+                    identifier: identifier.into_substituted(params, regex_escape),
+                    url: url.map(|s| s.into_substituted(params, regex_escape)),
+                })
+            }
+            PackageLicense::String(s) => {
+                PackageLicense::String(s.into_substituted(params, regex_escape))
+            }
+            license @ PackageLicense::Object(_) => license,
+        }
+    }
+}
+
+impl Substitute for Psmodule {
+    fn into_substituted(self, params: &SubstitutionMap, regex_escape: bool) -> Self {
+        Psmodule {
+            name: self.name.map(|s| s.into_substituted(params, regex_escape)),
+        }
+    }
+}
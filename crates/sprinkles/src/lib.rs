@@ -19,6 +19,8 @@ pub use semver;
 pub mod buckets;
 #[cfg(feature = "manifest-hashes")]
 pub mod cache;
+#[cfg(feature = "manifest-hashes")]
+pub mod checkver;
 pub mod config;
 pub mod contexts;
 pub mod diagnostics;
@@ -27,6 +29,7 @@ pub mod git;
 pub mod hacks;
 #[cfg(feature = "manifest-hashes")]
 pub mod hash;
+pub mod offline;
 pub mod output;
 pub mod packages;
 pub mod progress;
@@ -38,6 +41,8 @@ pub mod stream;
 pub mod version;
 
 mod env;
+#[cfg(test)]
+mod test_server;
 
 #[doc(hidden)]
 pub mod __versions {
@@ -58,15 +63,11 @@ extern crate log;
 
 use contexts::Error;
 
-/// Ensure supported environment
-mod const_assertions {
-    const _: () = assert!(cfg!(windows), "Only windows is supported");
-}
-
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, ListVariants)]
 /// Supported architectures
 pub enum Architecture {
     /// 64 bit Arm
+    #[serde(rename = "arm64", alias = "Arm64")]
     Arm64,
     /// 64 bit
     #[serde(rename = "64bit")]
@@ -151,18 +152,35 @@ mod tests {
     use itertools::Itertools;
 
     use crate::{
-        contexts::{ScoopContext, User},
+        contexts::{Fixture, ScoopContext},
         packages::{CreateManifest, InstallManifest},
     };
 
     #[test]
     fn test_list_install_manifests() {
-        let ctx = User::new();
+        let dir = tempfile::tempdir().unwrap();
+        let install = InstallManifest {
+            bucket: Some("main".into()),
+            ..Default::default()
+        };
+        let ctx = Fixture::builder(dir.path())
+            .app("sfsu", "1.0.0", r#"{"version":"1.0.0"}"#, &install)
+            .build()
+            .unwrap();
+
         let app_paths = ctx.installed_apps().unwrap();
 
-        app_paths
+        let manifests = app_paths
             .into_iter()
             .map(|path| InstallManifest::from_path(path.join("current/install.json")).unwrap())
             .collect_vec();
+
+        assert_eq!(
+            manifests
+                .iter()
+                .map(|manifest| manifest.bucket.as_deref())
+                .collect_vec(),
+            vec![install.bucket.as_deref()]
+        );
     }
 }
@@ -0,0 +1,19 @@
+//! Offline mode, where nothing accesses the network
+//!
+//! Anything that would access the network fails early with an error instead,
+//! so callers can fall back to local information.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable offline mode, for the rest of the process
+pub fn set(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+#[must_use]
+/// Check if offline mode is enabled
+pub fn enabled() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
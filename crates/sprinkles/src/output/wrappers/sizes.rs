@@ -2,12 +2,20 @@
 
 use std::cmp::min;
 use std::fmt::Display;
+use std::str::FromStr;
 
 use serde::Serialize;
 
 const SUFFIX: [&str; 9] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB", "ZiB", "YiB"];
 const UNIT: f64 = 1024.0;
 
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "Invalid size: {0}. Expected a number of bytes, optionally followed by a unit, e.g. 500MiB"
+)]
+/// An error parsing a size
+pub struct ParseError(String);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 /// A size in bytes.
 pub struct Size(u64);
@@ -18,6 +26,46 @@ impl Size {
     pub fn new(size: u64) -> Self {
         Self(size)
     }
+
+    #[must_use]
+    /// Get the size in bytes.
+    pub fn bytes(self) -> u64 {
+        self.0
+    }
+}
+
+impl FromStr for Size {
+    type Err = ParseError;
+
+    /// Parse a size with an optional unit, e.g. `1024`, `500MiB` or `2 GB`.
+    ///
+    /// Units are case insensitive, and are always treated as powers of 1024.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseError(s.to_string());
+
+        let trimmed = s.trim();
+        let (number, unit) = trimmed.split_at(
+            trimmed
+                .find(|c: char| !c.is_ascii_digit() && c != '.')
+                .unwrap_or(trimmed.len()),
+        );
+
+        let number = number.parse::<f64>().map_err(|_| error())?;
+
+        let unit = unit.trim().to_lowercase();
+        let unit = unit.trim_end_matches("ib").trim_end_matches('b');
+        let power = match unit {
+            "" => 0,
+            "k" => 1,
+            "m" => 2,
+            "g" => 3,
+            "t" => 4,
+            _ => return Err(error()),
+        };
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Ok(Self((number * UNIT.powi(power)) as u64))
+    }
 }
 
 impl std::ops::Add for Size {
@@ -83,4 +131,15 @@ mod tests {
         //     "1.00 YiB"
         // );
     }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!("1024".parse::<Size>().unwrap(), Size(1024));
+        assert_eq!("1KiB".parse::<Size>().unwrap(), Size(1024));
+        assert_eq!("500 mb".parse::<Size>().unwrap(), Size(500 * 1024 * 1024));
+        assert_eq!("1.5G".parse::<Size>().unwrap(), Size(1536 * 1024 * 1024));
+
+        assert!("".parse::<Size>().is_err());
+        assert!("10 apples".parse::<Size>().is_err());
+    }
 }
@@ -1,6 +1,7 @@
 //! Scoop package helpers
 
 use std::{
+    collections::HashMap,
     path::Path,
     process::Stdio,
     time::{SystemTimeError, UNIX_EPOCH},
@@ -21,7 +22,6 @@ use crate::{
     config,
     contexts::ScoopContext,
     git::{self, errors, Repo},
-    let_chain,
     output::{
         sectioned::{Children, Section, Text},
         wrappers::{author::Author, time::NicerTime},
@@ -38,7 +38,11 @@ use crate::{
     packages::manifest::TOrArrayOfTs,
 };
 
+pub mod dependencies;
 pub mod downloading;
+pub mod format;
+#[cfg(feature = "manifest-hashes")]
+pub mod lint;
 pub mod models;
 pub mod reference;
 
@@ -526,7 +530,9 @@ impl InstallManifest {
     pub fn list_all(ctx: &impl ScoopContext<config::Scoop>) -> Result<Vec<Self>> {
         ctx.installed_apps()?
             .par_iter()
-            .map(|path| Self::from_path(path.join("current/install.json")))
+            .map(|path| {
+                Self::from_path(path.join("current/install.json")).map(|v| v.with_name(path))
+            })
             .collect::<Result<Vec<_>>>()
     }
 
@@ -583,6 +589,33 @@ impl Manifest {
         )
     }
 
+    #[must_use]
+    /// Get the `Cookie` header to send with download requests, from the manifest `cookie` field
+    pub fn cookie_header(&self) -> Option<String> {
+        let cookies = self.cookie.as_ref()?;
+
+        let mut cookies = cookies
+            .iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    Some(serde_json::Value::String(value)) => value.clone(),
+                    Some(value) => value.to_string(),
+                    None => String::new(),
+                };
+
+                format!("{name}={value}")
+            })
+            .collect::<Vec<_>>();
+
+        if cookies.is_empty() {
+            return None;
+        }
+
+        cookies.sort();
+
+        Some(cookies.join("; "))
+    }
+
     #[must_use]
     /// Apply a bucket to a manifest
     pub fn with_bucket(mut self, bucket: &Bucket) -> Self {
@@ -752,136 +785,162 @@ impl Manifest {
         is_installed(ctx, &self.name, bucket)
     }
 
-    fn update_field<T>(
-        arch_field: Option<&mut T>,
-        default_field: &mut Option<T>,
-        value: Option<T>,
+    #[cfg(feature = "manifest-hashes")]
+    /// Substitute the version and checkver captures into every field that the autoupdate config updates, except for hashes
+    ///
+    /// Fields are updated where the manifest already defines them,
+    /// either at the top level or in an architecture specific block.
+    /// Fields that are not defined anywhere in the manifest are added to the top level.
+    fn substitute_autoupdate(
+        &mut self,
+        autoupdate: &manifest::Autoupdate,
+        matches: &HashMap<String, String>,
     ) {
-        let_chain!(let Some(arch_field) = arch_field; let Some(value) = value; {
-            *arch_field = value;
-        }; else {
-            *default_field = value;
-        });
-        // if let Some(arch_field) = arch_field
-        //     && let Some(value) = value
-        // {
-        //     *arch_field = value;
-        // } else {
-        //     *default_field = value;
-        // }
-    }
+        use quork::traits::list::ListVariants;
 
-    #[cfg(feature = "manifest-hashes")]
-    fn get_new_urls(&self, autoupdate: &AutoupdateConfig) -> Option<TOrArrayOfTs<String>> {
-        use crate::hash::substitutions::Substitute;
+        let mut submap = SubstitutionMap::new();
+        submap.append_version(&self.version);
+        submap.append_matches(matches);
 
-        if let Some(autoupdate_urls) = &autoupdate.url {
-            debug!("Autoupdate Urls: {autoupdate_urls}");
+        macro_rules! update_field {
+            ($field:ident) => {{
+                let in_architecture = self.architecture.as_ref().is_some_and(|config| {
+                    Architecture::VARIANTS
+                        .into_iter()
+                        .any(|arch| arch_field!(arch => config.$field as ref).is_some())
+                });
 
-            let mut submap = SubstitutionMap::new();
-            submap.append_version(&self.version);
+                if self.install_config.$field.is_some() || !in_architecture {
+                    if let Some(value) = &autoupdate.default_config.$field {
+                        self.install_config.$field =
+                            Some(value.clone().into_substituted(&submap, false));
+                    }
+                }
 
-            let new_urls = autoupdate_urls
-                .clone()
-                .to_vec()
-                .into_iter()
-                .map(|url| url.into_substituted(&submap, false));
+                if let Some(config) = &mut self.architecture {
+                    for arch in Architecture::VARIANTS {
+                        let arch_autoupdate = autoupdate
+                            .architecture
+                            .merge_default(autoupdate.default_config.clone(), arch);
 
-            Some(new_urls.collect())
-        } else {
-            None
+                        if let Some(field) = arch_field!(arch => config.$field as mut) {
+                            if let Some(value) = arch_autoupdate.$field {
+                                *field = value.into_substituted(&submap, false);
+                            }
+                        }
+                    }
+                }
+            }};
+        }
+
+        update_field!(url);
+        update_field!(bin);
+        update_field!(extract_dir);
+        update_field!(installer);
+        update_field!(shortcuts);
+
+        // The manifest only supports these at the top level
+        let substitute = |value: &StringArray| value.clone().into_substituted(&submap, false);
+
+        if let Some(env_add_path) = &autoupdate.default_config.env_add_path {
+            self.env_add_path = Some(substitute(env_add_path));
+        }
+        if let Some(notes) = &autoupdate.notes {
+            self.notes = Some(substitute(notes));
+        }
+        if let Some(env_set) = &autoupdate.default_config.env_set {
+            self.env_set = Some(env_set.clone().into_substituted(&submap, false));
+        }
+        if let Some(persist) = &autoupdate.persist {
+            self.persist = Some(persist.clone().into_substituted(&submap, false));
+        }
+        if let Some(license) = &autoupdate.license {
+            self.license = Some(
+                manifest::PackageLicense::from(license.clone()).into_substituted(&submap, false),
+            );
+        }
+        if let Some(psmodule) = &autoupdate.psmodule {
+            self.psmodule =
+                Some(manifest::Psmodule::from(psmodule.clone()).into_substituted(&submap, false));
         }
     }
 
     #[cfg(feature = "manifest-hashes")]
-    /// Set the manifest version and get the hash for the manifest
+    /// Update the manifest to the given version, using its autoupdate config
+    ///
+    /// The checkver captures (i.e `$matchBuild`) are substituted alongside the version.
+    /// Hashes are fetched for each url, and stored alongside it,
+    /// so architectures with their own urls get their own hashes.
     ///
     /// # Errors
     /// - Missing autoupdate field
     /// - Hash error
-    pub async fn set_version(
+    pub async fn autoupdate_to(
         &mut self,
         ctx: &impl ScoopContext<config::Scoop>,
         version: String,
+        matches: &HashMap<String, String>,
     ) -> Result<(), Error> {
         use quork::traits::list::ListVariants;
 
         use crate::hash::Hash;
 
-        self.version = version.into();
+        let autoupdate = self.autoupdate.clone().ok_or(Error::MissingAutoUpdate)?;
 
-        let autoupdate = self.autoupdate.as_ref().ok_or(Error::MissingAutoUpdate)?;
+        self.version = version.into();
+        self.substitute_autoupdate(&autoupdate, matches);
 
-        // TODO: This sets the same hash and url for all architectures
-        for arch in crate::Architecture::VARIANTS {
-            let arch_autoupdate = autoupdate
+        let has_own_url = |manifest: &Self, arch: Architecture| {
+            manifest
                 .architecture
-                .merge_default(autoupdate.default_config.clone(), arch);
-
-            let arch_url = self.get_new_urls(&arch_autoupdate);
+                .as_ref()
+                .is_some_and(|config| arch_field!(arch => config.url as ref).is_some())
+        };
 
-            if let Some(arch_config) = &mut self.architecture {
-                Self::update_field(
-                    arch_field!(arch => arch_config.url as mut),
-                    &mut self.install_config.url,
-                    arch_url,
-                );
-            } else {
-                self.install_config.url = self.get_new_urls(&autoupdate.default_config);
-            }
-        }
+        if self.install_config.url.is_some() {
+            // Prefer an architecture that actually uses the top level url
+            let arch = Architecture::VARIANTS
+                .into_iter()
+                .find(|arch| !has_own_url(self, *arch))
+                .unwrap_or(Architecture::ARCH);
 
-        macro_rules! update_field {
-            ($field:ident) => {{
-                let mut submap = SubstitutionMap::new();
-                submap.append_version(&self.version);
-
-                for arch in crate::Architecture::VARIANTS {
-                    if let Some(config) = &mut self.architecture {
-                        let default = arch_field!(arch => config.$field as cloned).map(|s| s.into_substituted(&submap, false));
-
-                        Self::update_field(
-                            arch_field!(arch => config.$field as mut),
-                            &mut self.install_config.$field,
-                            default,
-                        );
-                    } else {
-                        self.install_config.$field = autoupdate.default_config.$field.clone().map(|s| s.into_substituted(&submap, false));
-                    }
-                }
-            }}
+            let hashes = Hash::get_for_app_with_matches(ctx, self, arch, matches).await?;
+            self.install_config.hash = TOrArrayOfTs::from_vec(hashes);
         }
 
-        update_field!(bin);
-        update_field!(extract_dir);
-        update_field!(installer);
-        update_field!(shortcuts);
-
-        for arch in crate::Architecture::VARIANTS {
-            let Ok(hashes) = Hash::get_for_app(ctx, self, arch).await else {
+        for arch in Architecture::VARIANTS {
+            if !has_own_url(self, arch) {
                 continue;
-            };
+            }
 
-            if let Some(arch_config) = &mut self.architecture {
-                // TODO: This sets the same hash and url for all architectures
-                Self::update_field(
-                    arch_field!(arch => arch_config.hash as mut),
-                    &mut self.install_config.hash,
-                    TOrArrayOfTs::from_vec(hashes),
-                );
-            } else {
-                self.install_config.hash = TOrArrayOfTs::from_vec(hashes);
+            let hashes = Hash::get_for_app_with_matches(ctx, self, arch, matches).await?;
+
+            if let Some(config) = self
+                .architecture
+                .as_mut()
+                .and_then(|config| config[arch].as_mut())
+            {
+                config.hash = TOrArrayOfTs::from_vec(hashes);
             }
         }
 
-        // TODO: Handle other autoupdate fields
-        // TODO: Autoupdate fields in all architectures
-        // todo!("Handle urls and other autoupdate fields");
-
-        // TODO: Figure out hash extraction
-        // autoupdate_arch.hash
+        Ok(())
+    }
 
-        // todo!()
+    #[cfg(feature = "manifest-hashes")]
+    /// Set the manifest version and get the hash for the manifest
+    ///
+    /// The updated manifest is written to the workspace
+    ///
+    /// # Errors
+    /// - Missing autoupdate field
+    /// - Hash error
+    pub async fn set_version(
+        &mut self,
+        ctx: &impl ScoopContext<config::Scoop>,
+        version: String,
+    ) -> Result<(), Error> {
+        self.autoupdate_to(ctx, version, &HashMap::new()).await?;
 
         let workspace_manifest_path = ctx.workspace_path().join(format!("{}.json", self.name));
         serde_json::to_writer_pretty(std::fs::File::create(workspace_manifest_path)?, &self)
@@ -893,6 +952,49 @@ impl Manifest {
         Ok(())
     }
 
+    /// Write the manifest to the given path, with 4 space indentation
+    ///
+    /// If a manifest already exists at the path, its key order is kept,
+    /// and any keys that sfsu does not know about are left untouched.
+    /// New keys are added after the existing ones.
+    ///
+    /// # Errors
+    /// - Reading or writing the manifest file
+    /// - Invalid existing manifest
+    pub fn write_to(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let parsing_error = |e| Error::ParsingManifest(self.name.clone(), e);
+
+        let mut value = serde_json::to_value(self).map_err(parsing_error)?;
+
+        if path.exists() {
+            let existing = std::fs::read_to_string(path)?;
+            let existing = serde_json::from_str(existing.trim_start_matches('\u{feff}'))
+                .map_err(parsing_error)?;
+
+            value = merge_ordered(existing, value);
+        }
+
+        let output = format::to_string_pretty(&value).map_err(parsing_error)?;
+
+        std::fs::write(path, output)?;
+
+        Ok(())
+    }
+
+    /// Write the manifest back to its file in its bucket
+    ///
+    /// See [`Manifest::write_to`] for how the existing file is preserved
+    ///
+    /// # Errors
+    /// - Invalid bucket
+    /// - Reading or writing the manifest file
+    pub fn write_to_bucket(&self, ctx: &impl ScoopContext<config::Scoop>) -> Result<()> {
+        let bucket = Bucket::from_name(ctx, &self.bucket)?;
+
+        self.write_to(bucket.get_manifest_path(&self.name))
+    }
+
     #[must_use]
     /// Check if the commit's message matches the name of the manifest
     pub fn commit_message_matches(&self, commit: &Commit<'_>) -> bool {
@@ -1092,6 +1194,37 @@ pub fn is_installed(
     }
 }
 
+/// Merge an updated manifest into an existing one, keeping the existing key order
+///
+/// Keys missing from the updated manifest are kept as is, so that unknown fields are not lost
+fn merge_ordered(existing: serde_json::Value, updated: serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+
+    match (existing, updated) {
+        (Value::Object(existing), Value::Object(mut updated)) => {
+            let mut merged = serde_json::Map::new();
+
+            for (key, value) in existing {
+                let value = match updated.get_mut(&key) {
+                    Some(updated) => merge_ordered(value, updated.take()),
+                    None => value,
+                };
+
+                merged.insert(key, value);
+            }
+
+            for (key, value) in updated {
+                if !merged.contains_key(&key) {
+                    merged.insert(key, value);
+                }
+            }
+
+            Value::Object(merged)
+        }
+        (_, updated) => updated,
+    }
+}
+
 /// Merge defaults for a given architecture and the provided field
 pub trait MergeDefaults {
     /// Output & Input type
@@ -1189,6 +1322,7 @@ mod tests {
     use rayon::prelude::*;
 
     #[test]
+    #[cfg_attr(not(windows), ignore = "Requires a Scoop install with buckets added")]
     fn test_parse_all_manifests() -> Result<(), Box<dyn Error>> {
         const UNSUPPORTED_PACKAGES: &[&str] = &["unityhub"];
 
@@ -1217,4 +1351,107 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_merge_ordered() {
+        let existing = serde_json::json!({
+            "version": "1.0.0",
+            "unknown": true,
+            "architecture": {
+                "64bit": { "url": "old", "hash": "old" }
+            }
+        });
+        let updated = serde_json::json!({
+            "architecture": {
+                "64bit": { "hash": "new", "url": "new" }
+            },
+            "notes": "added",
+            "version": "2.0.0"
+        });
+
+        let merged = super::merge_ordered(existing, updated);
+
+        assert_eq!(
+            serde_json::to_string(&merged).unwrap(),
+            r#"{"version":"2.0.0","unknown":true,"architecture":{"64bit":{"url":"new","hash":"new"}},"notes":"added"}"#
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "manifest-hashes")]
+    fn test_substitute_autoupdate() {
+        const MANIFEST: &str = r#"{"version":"0.10.1","license":"MIT","notes":"Version 0.10.1","architecture":{"64bit":{"url":"https://ziglang.org/download/0.10.1/zig-windows-x86_64-0.10.1.zip","extract_dir":"zig-windows-x86_64-0.10.1"},"arm64":{"url":"https://ziglang.org/download/0.10.1/zig-windows-aarch64-0.10.1.zip","extract_dir":"zig-windows-aarch64-0.10.1"}},"bin":"zig.exe","env_add_path":"zig-0.10.1","autoupdate":{"notes":"Version $version","env_add_path":"zig-$version","architecture":{"64bit":{"url":"https://ziglang.org/download/$version/zig-windows-x86_64-$version.zip","extract_dir":"zig-windows-x86_64-$version"},"arm64":{"url":"https://ziglang.org/download/$version/zig-windows-aarch64-$version.zip","extract_dir":"zig-windows-aarch64-$version"}}}}"#;
+
+        let mut manifest: super::Manifest = serde_json::from_str(MANIFEST).unwrap();
+        let autoupdate = manifest.autoupdate.clone().unwrap();
+
+        manifest.version = "0.11.0".to_string().into();
+        manifest.substitute_autoupdate(&autoupdate, &std::collections::HashMap::new());
+
+        let architecture = manifest.architecture.as_ref().unwrap();
+        let x64 = architecture[Architecture::X64].as_ref().unwrap();
+        let arm64 = architecture[Architecture::Arm64].as_ref().unwrap();
+
+        assert_eq!(
+            x64.url.as_ref().unwrap().to_string(),
+            "https://ziglang.org/download/0.11.0/zig-windows-x86_64-0.11.0.zip"
+        );
+        assert_eq!(
+            arm64.extract_dir.as_ref().unwrap().to_string(),
+            "zig-windows-aarch64-0.11.0"
+        );
+        assert!(architecture[Architecture::X86].is_none());
+        assert!(manifest.install_config.url.is_none());
+        assert_eq!(manifest.notes.unwrap().to_string(), "Version 0.11.0");
+        assert_eq!(manifest.env_add_path.unwrap().to_string(), "zig-0.11.0");
+        assert_eq!(manifest.install_config.bin.unwrap().to_string(), "zig.exe");
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "manifest-hashes")]
+    async fn test_autoupdate_to_with_matches() {
+        use crate::{
+            contexts::Fixture,
+            test_server::{Response, TestServer},
+        };
+
+        const HASH: &str = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/2.0.0/456/app.zip.sha256" => Response::ok(HASH),
+            _ => Response::status(404),
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = Fixture::builder(dir.path()).build().unwrap();
+
+        let mut manifest: super::Manifest = serde_json::from_value(serde_json::json!({
+            "version": "1.0.0",
+            "url": server.url("/1.0.0/123/app.zip"),
+            "hash": "0".repeat(64),
+            "extract_dir": "app-123",
+            "autoupdate": {
+                "url": server.url("/$version/$matchBuild/app.zip"),
+                "hash": { "url": "$url.sha256" },
+                "extract_dir": "app-$matchBuild"
+            }
+        }))
+        .unwrap();
+
+        let matches = [("$matchBuild".to_string(), "456".to_string())].into();
+        manifest
+            .autoupdate_to(&ctx, "2.0.0".into(), &matches)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            manifest.install_config.url.unwrap().to_string(),
+            server.url("/2.0.0/456/app.zip")
+        );
+        assert_eq!(
+            manifest.install_config.extract_dir.unwrap().to_string(),
+            "app-456"
+        );
+        assert_eq!(manifest.install_config.hash.unwrap().to_string(), HASH);
+    }
 }
@@ -0,0 +1,520 @@
+//! Recursive dependency resolution
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use itertools::Itertools;
+use serde::Serialize;
+
+use super::{
+    reference::{self, ManifestRef, Package},
+    InstallManifest, Manifest,
+};
+use crate::{config, contexts::ScoopContext, version::Version};
+
+mod render;
+
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+/// Dependency resolution errors
+pub enum Error {
+    #[error("Dependency cycle detected: {}", .0.iter().join(" -> "))]
+    Cycle(Vec<String>),
+    #[error("Could not resolve dependency '{0}': {1}")]
+    Resolving(ManifestRef, reference::Error),
+    #[error("Invalid app name in dependency '{0}'")]
+    MissingAppName(ManifestRef),
+    #[error("Package reference error: {0}")]
+    Reference(#[from] reference::Error),
+    #[error("Packages error: {0}")]
+    Packages(#[from] super::Error),
+}
+
+/// Dependency resolution result type
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Clone, Serialize)]
+/// A single package in a dependency graph
+pub struct Node {
+    /// The package name
+    pub name: String,
+    /// The bucket the manifest was resolved from
+    ///
+    /// This will be empty if the manifest was not loaded from a bucket
+    pub bucket: String,
+    /// The manifest version
+    pub version: Version,
+    /// If the package is already installed
+    pub installed: bool,
+    /// The names of the direct dependencies of the package
+    pub depends: Vec<String>,
+}
+
+impl Node {
+    #[must_use]
+    /// Get a reference to the package, qualified with the bucket if known
+    pub fn reference(&self) -> ManifestRef {
+        if self.bucket.is_empty() {
+            ManifestRef::Name(self.name.clone())
+        } else {
+            ManifestRef::BucketNamePair {
+                bucket: self.bucket.clone(),
+                name: self.name.clone(),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+/// A resolved dependency graph
+pub struct Graph {
+    /// The names of the packages the graph was resolved from
+    pub roots: Vec<String>,
+    /// Every package in the graph, in the order that they will be installed
+    pub nodes: Vec<Node>,
+}
+
+impl Graph {
+    /// Recursively resolve the dependencies of the given manifests
+    ///
+    /// Dependencies are looked up across all local buckets, unless they are bucket qualified
+    ///
+    /// # Errors
+    /// - A dependency could not be found or parsed
+    /// - A dependency has an invalid name
+    /// - The dependencies contain a cycle
+    /// - The installed apps could not be read
+    pub async fn resolve(
+        ctx: &impl ScoopContext<config::Scoop>,
+        roots: impl IntoIterator<Item = Manifest>,
+    ) -> Result<Self> {
+        let mut manifests = HashMap::new();
+        let mut root_names = vec![];
+        let mut queue = VecDeque::new();
+
+        for manifest in roots {
+            root_names.push(manifest.name.clone());
+            queue.extend(manifest.depends());
+            manifests.insert(manifest.name.clone(), manifest);
+        }
+
+        while let Some(dependency) = queue.pop_front() {
+            let name = dependency_name(&dependency)?;
+
+            if manifests.contains_key(&name) {
+                continue;
+            }
+
+            let manifest = Package::from_ref(dependency.clone())
+                .manifest(ctx)
+                .await
+                .map_err(|e| Error::Resolving(dependency, e))?;
+
+            queue.extend(manifest.depends());
+            manifests.insert(name, manifest);
+        }
+
+        let mut edges = manifests
+            .iter()
+            .map(|(name, manifest)| {
+                let depends = manifest
+                    .depends()
+                    .iter()
+                    .map(dependency_name)
+                    .collect::<Result<Vec<_>>>()?;
+
+                Ok((name.clone(), depends))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        let nodes = install_order(&root_names, &edges)?
+            .into_iter()
+            .map(|name| {
+                let manifest = manifests
+                    .remove(&name)
+                    .expect("every sorted package to have been resolved");
+
+                let mut node = Node {
+                    depends: edges.remove(&name).unwrap_or_default(),
+                    name,
+                    bucket: manifest.bucket,
+                    version: manifest.version,
+                    installed: false,
+                };
+                node.installed = Package::from_ref(node.reference()).installed(ctx)?;
+
+                Ok(node)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            roots: root_names,
+            nodes,
+        })
+    }
+
+    /// Resolve the dependency graph of every installed package
+    ///
+    /// Installed packages use their installed manifest, and any dependencies that are not installed are looked up across all local buckets
+    ///
+    /// # Errors
+    /// - The installed apps could not be read
+    /// - An install manifest or installed manifest is invalid
+    /// - A dependency could not be found or parsed
+    /// - The dependencies contain a cycle
+    pub async fn installed(ctx: &impl ScoopContext<config::Scoop>) -> Result<Self> {
+        let mut manifests = InstallManifest::list_all(ctx)?
+            .into_iter()
+            .map(|install_manifest| {
+                let mut manifest = install_manifest.get_manifest(ctx)?;
+                manifest.bucket = install_manifest.bucket.unwrap_or_default();
+
+                Ok(manifest)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        manifests.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Self::resolve(ctx, manifests).await
+    }
+
+    /// Iterate over every package in the graph, excluding the roots, in the order that they will be installed
+    pub fn dependencies(&self) -> impl Iterator<Item = &Node> {
+        self.nodes
+            .iter()
+            .filter(|node| !self.roots.contains(&node.name))
+    }
+
+    #[must_use]
+    /// Get a package in the graph by name
+    pub fn get(&self, name: impl AsRef<str>) -> Option<&Node> {
+        let name = name.as_ref();
+
+        self.nodes.iter().find(|node| node.name == name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// An installed package that depends on another package
+pub struct Dependent {
+    /// The bucket qualified reference to the dependent package
+    pub reference: ManifestRef,
+    /// If the package lists the dependency in its own manifest
+    ///
+    /// Otherwise it depends on it through another installed package
+    pub direct: bool,
+}
+
+#[derive(Debug, Clone)]
+struct InstalledApp {
+    bucket: Option<String>,
+    depends: Vec<ManifestRef>,
+}
+
+impl InstalledApp {
+    fn reference(&self, name: &str) -> ManifestRef {
+        match &self.bucket {
+            Some(bucket) => ManifestRef::BucketNamePair {
+                bucket: bucket.clone(),
+                name: name.to_string(),
+            },
+            None => ManifestRef::Name(name.to_string()),
+        }
+    }
+
+    fn depends_on(&self, name: &str, bucket: Option<&str>) -> bool {
+        self.depends.iter().any(|dependency| match dependency {
+            ManifestRef::BucketNamePair {
+                bucket: required_bucket,
+                name: required_name,
+            } => {
+                required_name.eq_ignore_ascii_case(name)
+                    && bucket.map_or(true, |bucket| required_bucket.eq_ignore_ascii_case(bucket))
+            }
+            dependency => dependency_name(dependency)
+                .is_ok_and(|required_name| required_name.eq_ignore_ascii_case(name)),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// An index of installed packages, used to find which packages depend on a given package
+pub struct ReverseIndex {
+    installed: BTreeMap<String, InstalledApp>,
+}
+
+impl ReverseIndex {
+    /// Build the index from the installed packages
+    ///
+    /// Installed packages with an invalid install manifest or installed manifest are skipped
+    ///
+    /// # Errors
+    /// - The installed apps could not be read
+    pub fn load(ctx: &impl ScoopContext<config::Scoop>) -> Result<Self> {
+        let installed = InstallManifest::list_all_unchecked(ctx)?
+            .into_iter()
+            .filter_map(
+                |install_manifest| match install_manifest.get_manifest(ctx) {
+                    Ok(manifest) => Some((
+                        install_manifest.name,
+                        InstalledApp {
+                            bucket: install_manifest.bucket,
+                            depends: manifest.depends(),
+                        },
+                    )),
+                    Err(e) => {
+                        warn!(
+                            "Skipping {} when finding dependents: {e}",
+                            install_manifest.name
+                        );
+                        None
+                    }
+                },
+            )
+            .collect();
+
+        Ok(Self { installed })
+    }
+
+    #[must_use]
+    /// Find every installed package that depends on the given package, either directly or transitively
+    ///
+    /// If the package is bucket qualified, dependencies on the same package name from a different bucket are ignored.
+    /// Otherwise the bucket the package was installed from is used, if it is installed.
+    pub fn dependents(&self, package: &Package) -> Vec<Dependent> {
+        let Some(name) = package.name() else {
+            return vec![];
+        };
+
+        let bucket = package
+            .bucket()
+            .map(ToString::to_string)
+            .or_else(|| self.installed_bucket(&name));
+
+        let mut seen = HashSet::from([name.to_lowercase()]);
+        let mut queue = VecDeque::from([(name, bucket, true)]);
+        let mut dependents = vec![];
+
+        while let Some((name, bucket, direct)) = queue.pop_front() {
+            for (dependent_name, app) in &self.installed {
+                if !app.depends_on(&name, bucket.as_deref())
+                    || !seen.insert(dependent_name.to_lowercase())
+                {
+                    continue;
+                }
+
+                dependents.push(Dependent {
+                    reference: app.reference(dependent_name),
+                    direct,
+                });
+                queue.push_back((dependent_name.clone(), app.bucket.clone(), false));
+            }
+        }
+
+        dependents
+    }
+
+    fn installed_bucket(&self, name: &str) -> Option<String> {
+        self.installed
+            .iter()
+            .find(|(installed_name, _)| installed_name.eq_ignore_ascii_case(name))
+            .and_then(|(_, app)| app.bucket.clone())
+    }
+}
+
+/// Find every installed package that depends on the given package, either directly or transitively
+///
+/// # Errors
+/// - The installed apps could not be read
+/// - An install manifest or installed manifest is invalid
+pub fn dependents(
+    ctx: &impl ScoopContext<config::Scoop>,
+    package: &Package,
+) -> Result<Vec<Dependent>> {
+    Ok(ReverseIndex::load(ctx)?.dependents(package))
+}
+
+fn dependency_name(dependency: &ManifestRef) -> Result<String> {
+    Package::from_ref(dependency.clone())
+        .name()
+        .ok_or_else(|| Error::MissingAppName(dependency.clone()))
+}
+
+/// Sort the packages so that every package comes after all of its dependencies
+fn install_order(roots: &[String], edges: &HashMap<String, Vec<String>>) -> Result<Vec<String>> {
+    fn visit(
+        name: &str,
+        edges: &HashMap<String, Vec<String>>,
+        visited: &mut HashSet<String>,
+        stack: &mut Vec<String>,
+        order: &mut Vec<String>,
+    ) -> Result<()> {
+        if visited.contains(name) {
+            return Ok(());
+        }
+
+        if let Some(position) = stack.iter().position(|visiting| visiting == name) {
+            let mut cycle = stack[position..].to_vec();
+            cycle.push(name.to_string());
+
+            return Err(Error::Cycle(cycle));
+        }
+
+        stack.push(name.to_string());
+        for dependency in edges.get(name).into_iter().flatten() {
+            visit(dependency, edges, visited, stack, order)?;
+        }
+        stack.pop();
+
+        visited.insert(name.to_string());
+        order.push(name.to_string());
+
+        Ok(())
+    }
+
+    let mut visited = HashSet::new();
+    let mut stack = vec![];
+    let mut order = vec![];
+
+    for root in roots {
+        visit(root, edges, &mut visited, &mut stack, &mut order)?;
+    }
+
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edges(pairs: &[(&str, &[&str])]) -> HashMap<String, Vec<String>> {
+        pairs
+            .iter()
+            .map(|(name, depends)| {
+                (
+                    (*name).to_string(),
+                    depends.iter().map(ToString::to_string).collect(),
+                )
+            })
+            .collect()
+    }
+
+    fn index(apps: &[(&str, &str, &[&str])]) -> ReverseIndex {
+        ReverseIndex {
+            installed: apps
+                .iter()
+                .map(|(name, bucket, depends)| {
+                    (
+                        (*name).to_string(),
+                        InstalledApp {
+                            bucket: Some((*bucket).to_string()),
+                            depends: depends.iter().map(|dep| dep.parse().unwrap()).collect(),
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_install_order() {
+        let edges = edges(&[
+            ("app", &["runtime", "lessmsi"]),
+            ("runtime", &["vcredist", "lessmsi"]),
+            ("vcredist", &[]),
+            ("lessmsi", &[]),
+        ]);
+
+        let order = install_order(&["app".to_string()], &edges).unwrap();
+
+        assert_eq!(order, ["vcredist", "lessmsi", "runtime", "app"]);
+    }
+
+    #[test]
+    fn test_detects_cycles() {
+        let edges = edges(&[("a", &["b"]), ("b", &["c"]), ("c", &["a"])]);
+
+        let Err(Error::Cycle(cycle)) = install_order(&["a".to_string()], &edges) else {
+            panic!("expected a cycle error");
+        };
+
+        assert_eq!(cycle, ["a", "b", "c", "a"]);
+    }
+
+    #[test]
+    fn test_detects_self_dependency() {
+        let edges = edges(&[("a", &["a"])]);
+
+        assert!(matches!(
+            install_order(&["a".to_string()], &edges),
+            Err(Error::Cycle(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_skips_invalid_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        let install = InstallManifest {
+            bucket: Some("main".into()),
+            ..Default::default()
+        };
+        let ctx = crate::contexts::Fixture::builder(dir.path())
+            .app("7zip", "1.0.0", r#"{"version":"1.0.0"}"#, &install)
+            .app(
+                "lessmsi",
+                "1.0.0",
+                r#"{"version":"1.0.0","depends":"7zip"}"#,
+                &install,
+            )
+            .app("broken", "1.0.0", "not a manifest", &install)
+            .build()
+            .unwrap();
+
+        let dependents = ReverseIndex::load(&ctx)
+            .unwrap()
+            .dependents(&"7zip".parse().unwrap());
+
+        assert_eq!(
+            dependents
+                .iter()
+                .map(|dependent| dependent.reference.to_string())
+                .collect::<Vec<_>>(),
+            ["main/lessmsi"]
+        );
+    }
+
+    #[test]
+    fn test_transitive_dependents() {
+        let index = index(&[
+            ("7zip", "main", &[]),
+            ("lessmsi", "main", &["7zip"]),
+            ("app", "extras", &["main/lessmsi"]),
+            ("other", "extras", &[]),
+        ]);
+
+        let dependents = index.dependents(&"7zip".parse().unwrap());
+
+        assert_eq!(
+            dependents
+                .iter()
+                .map(|dependent| (dependent.reference.to_string(), dependent.direct))
+                .collect::<Vec<_>>(),
+            [
+                ("main/lessmsi".to_string(), true),
+                ("extras/app".to_string(), false)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dependents_respect_bucket() {
+        let index = index(&[
+            ("lessmsi", "main", &[]),
+            ("app", "extras", &["versions/lessmsi"]),
+        ]);
+
+        assert!(index.dependents(&"lessmsi".parse().unwrap()).is_empty());
+        assert_eq!(
+            index.dependents(&"versions/lessmsi".parse().unwrap()).len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! Dependency graph rendering
+
+use std::fmt::Write as _;
+
+use super::{Graph, Node};
+
+impl Graph {
+    #[must_use]
+    /// Render the graph in the Graphviz DOT format
+    ///
+    /// Packages that are not installed are drawn with a dashed outline
+    pub fn to_dot(&self) -> String {
+        let mut output = String::from("digraph dependencies {\n");
+
+        for node in &self.nodes {
+            let style = if node.installed { "" } else { ", style=dashed" };
+
+            _ = writeln!(
+                output,
+                "    \"{}\" [label=\"{}\"{style}];",
+                escape_dot(&node.name),
+                escape_dot(&label(node)),
+            );
+        }
+
+        for node in &self.nodes {
+            for dependency in &node.depends {
+                _ = writeln!(
+                    output,
+                    "    \"{}\" -> \"{}\";",
+                    escape_dot(&node.name),
+                    escape_dot(dependency),
+                );
+            }
+        }
+
+        output.push('}');
+
+        output
+    }
+
+    #[must_use]
+    /// Render the graph as a Mermaid flowchart
+    ///
+    /// Packages that are not installed are drawn with a dashed outline
+    pub fn to_mermaid(&self) -> String {
+        let mut output = String::from("flowchart TD\n");
+
+        // Mermaid ids are restricted, so use the node position instead of the package name
+        let id = |name: &str| {
+            self.nodes
+                .iter()
+                .position(|node| node.name == name)
+                .map(|position| format!("n{position}"))
+        };
+
+        for (position, node) in self.nodes.iter().enumerate() {
+            _ = writeln!(
+                output,
+                "    n{position}[\"{}\"]",
+                escape_mermaid(&label(node))
+            );
+        }
+
+        for node in &self.nodes {
+            let Some(from) = id(&node.name) else {
+                continue;
+            };
+
+            for dependency in &node.depends {
+                if let Some(to) = id(dependency) {
+                    _ = writeln!(output, "    {from} --> {to}");
+                }
+            }
+        }
+
+        let missing = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| !node.installed)
+            .map(|(position, _)| format!("n{position}"))
+            .collect::<Vec<_>>();
+
+        if !missing.is_empty() {
+            output.push_str("    classDef missing stroke-dasharray: 5 5\n");
+            _ = writeln!(output, "    class {} missing", missing.join(","));
+        }
+
+        output
+    }
+}
+
+fn label(node: &Node) -> String {
+    format!("{} ({})", node.reference(), node.version)
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> Graph {
+        let node = |name: &str, installed: bool, depends: &[&str]| Node {
+            name: name.to_string(),
+            bucket: "main".to_string(),
+            version: "1.0.0".to_string().into(),
+            installed,
+            depends: depends.iter().map(ToString::to_string).collect(),
+        };
+
+        Graph {
+            roots: vec!["app".to_string()],
+            nodes: vec![
+                node("7zip", true, &[]),
+                node("lessmsi", false, &["7zip"]),
+                node("app", true, &["lessmsi", "7zip"]),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_dot() {
+        assert_eq!(
+            graph().to_dot(),
+            r#"digraph dependencies {
+    "7zip" [label="main/7zip (1.0.0)"];
+    "lessmsi" [label="main/lessmsi (1.0.0)", style=dashed];
+    "app" [label="main/app (1.0.0)"];
+    "lessmsi" -> "7zip";
+    "app" -> "lessmsi";
+    "app" -> "7zip";
+}"#
+        );
+    }
+
+    #[test]
+    fn test_mermaid() {
+        assert_eq!(
+            graph().to_mermaid(),
+            r#"flowchart TD
+    n0["main/7zip (1.0.0)"]
+    n1["main/lessmsi (1.0.0)"]
+    n2["main/app (1.0.0)"]
+    n1 --> n0
+    n2 --> n1
+    n2 --> n0
+    classDef missing stroke-dasharray: 5 5
+    class n1 missing
+"#
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! Canonical manifest formatting, equivalent to Scoop's `formatjson` script
+//!
+//! Formatting works on the raw json, rather than a parsed [`Manifest`](super::Manifest),
+//! so that fields sfsu does not know about are kept as is.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use super::models::manifest::StringArray;
+
+/// The order of keys in a manifest, and in each architecture and autoupdate config
+///
+/// This follows the conventional order used across the official Scoop buckets.
+/// Keys not in this list are kept in their original order, after the known keys.
+pub const KEY_ORDER: &[&str] = &[
+    "##",
+    "$schema",
+    "_comment",
+    "version",
+    "description",
+    "homepage",
+    "license",
+    "notes",
+    "depends",
+    "suggest",
+    "architecture",
+    "url",
+    "hash",
+    "cookie",
+    "extract_dir",
+    "extract_to",
+    "innosetup",
+    "msi",
+    "pre_install",
+    "installer",
+    "post_install",
+    "bin",
+    "shortcuts",
+    "persist",
+    "env_add_path",
+    "env_set",
+    "psmodule",
+    "pre_uninstall",
+    "uninstaller",
+    "post_uninstall",
+    "checkver",
+    "autoupdate",
+];
+
+/// The order of architectures in a manifest
+pub const ARCHITECTURE_ORDER: &[&str] = &["64bit", "32bit", "arm64"];
+
+/// Keys that may be a single string, or an array of strings
+const STRING_ARRAYS: &[&str] = &[
+    "##",
+    "_comment",
+    "notes",
+    "depends",
+    "url",
+    "hash",
+    "extract_dir",
+    "extract_to",
+    "msi",
+    "pre_install",
+    "post_install",
+    "bin",
+    "persist",
+    "env_add_path",
+    "pre_uninstall",
+    "post_uninstall",
+];
+
+/// Format the manifest into its canonical form
+///
+/// - Keys are ordered by [`KEY_ORDER`] and [`ARCHITECTURE_ORDER`]
+/// - Arrays with a single string are collapsed into that string
+/// - Hashes are lowercase
+/// - Indentation is 4 spaces, with a trailing newline
+///
+/// The line endings of the original manifest are kept
+///
+/// # Errors
+/// - The manifest is not valid json
+pub fn format(contents: &str) -> serde_json::Result<String> {
+    let manifest = serde_json::from_str(contents.trim_start_matches('\u{feff}'))?;
+
+    let formatted = to_string_pretty(&format_value(manifest))?;
+
+    if contents.contains("\r\n") {
+        Ok(formatted.replace('\n', "\r\n"))
+    } else {
+        Ok(formatted)
+    }
+}
+
+#[must_use]
+/// Format a manifest's json value into its canonical form
+///
+/// See [`format`] for the rules that are applied
+pub fn format_value(mut manifest: Value) -> Value {
+    let Some(object) = manifest.as_object_mut() else {
+        return manifest;
+    };
+
+    format_config(object, true);
+
+    if let Some(Value::Object(autoupdate)) = object.get_mut("autoupdate") {
+        // Autoupdate hashes are extraction configs, rather than hashes
+        format_config(autoupdate, false);
+    }
+
+    manifest
+}
+
+/// Serialize a manifest with 4 space indentation, and a trailing newline
+///
+/// # Errors
+/// - Serializing the manifest fails
+pub fn to_string_pretty(manifest: &impl Serialize) -> serde_json::Result<String> {
+    let mut output = vec![];
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+    manifest.serialize(&mut serde_json::Serializer::with_formatter(
+        &mut output,
+        formatter,
+    ))?;
+    output.push(b'\n');
+
+    // The serializer only ever writes valid utf8
+    Ok(String::from_utf8(output).expect("valid utf8"))
+}
+
+fn format_config(config: &mut Map<String, Value>, is_hash: bool) {
+    for key in STRING_ARRAYS {
+        if let Some(value) = config.get_mut(*key) {
+            collapse(value);
+        }
+    }
+
+    for key in ["installer", "uninstaller"] {
+        if let Some(Value::Object(installer)) = config.get_mut(key) {
+            for key in ["args", "script"] {
+                if let Some(value) = installer.get_mut(key) {
+                    collapse(value);
+                }
+            }
+        }
+    }
+
+    if is_hash {
+        if let Some(hash) = config.get_mut("hash") {
+            lowercase(hash);
+        }
+    }
+
+    if let Some(Value::Object(architecture)) = config.get_mut("architecture") {
+        for arch_config in architecture.values_mut() {
+            if let Value::Object(arch_config) = arch_config {
+                format_config(arch_config, is_hash);
+            }
+        }
+
+        sort_keys(architecture, ARCHITECTURE_ORDER);
+    }
+
+    sort_keys(config, KEY_ORDER);
+}
+
+/// Collapse an array with a single string into that string
+fn collapse(value: &mut Value) {
+    // Anything that is not a string array, like a nested alias array, is left as is
+    let Ok(array) = serde_json::from_value::<StringArray>(value.clone()) else {
+        return;
+    };
+
+    let collapsed = StringArray::from_vec_or_default(array.to_vec());
+
+    if let Ok(collapsed) = serde_json::to_value(collapsed) {
+        *value = collapsed;
+    }
+}
+
+fn lowercase(value: &mut Value) {
+    match value {
+        Value::String(hash) => *hash = hash.to_lowercase(),
+        Value::Array(hashes) => hashes.iter_mut().for_each(lowercase),
+        _ => {}
+    }
+}
+
+fn sort_keys(object: &mut Map<String, Value>, order: &[&str]) {
+    let mut entries = std::mem::take(object).into_iter().collect::<Vec<_>>();
+
+    // The sort is stable, so unknown keys keep their original order
+    entries.sort_by_key(|(key, _)| {
+        order
+            .iter()
+            .position(|known| *known == key.as_str())
+            .unwrap_or(order.len())
+    });
+
+    object.extend(entries);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        const MANIFEST: &str = r#"{"autoupdate":{"hash":{"url":"$url.sha256"},"architecture":{"arm64":{"url":"https://example.com/$version/app-arm64.zip"},"64bit":{"url":["https://example.com/$version/app-x64.zip"]}}},"checkver":"github","custom":true,"bin":[["app.exe","app"]],"architecture":{"arm64":{"hash":"ABCDEF0123456789ABCDEF0123456789ABCDEF0123456789ABCDEF01234567","url":"https://example.com/1.0.0/app-arm64.zip"},"64bit":{"url":["https://example.com/1.0.0/app-x64.zip"],"hash":["sha1:ABCDEF0123456789ABCDEF0123456789ABCDEF01"]}},"version":"1.0.0","notes":["Single note"]}"#;
+
+        let expected = r#"{
+    "version": "1.0.0",
+    "notes": "Single note",
+    "architecture": {
+        "64bit": {
+            "url": "https://example.com/1.0.0/app-x64.zip",
+            "hash": "sha1:abcdef0123456789abcdef0123456789abcdef01"
+        },
+        "arm64": {
+            "url": "https://example.com/1.0.0/app-arm64.zip",
+            "hash": "abcdef0123456789abcdef0123456789abcdef0123456789abcdef01234567"
+        }
+    },
+    "bin": [
+        [
+            "app.exe",
+            "app"
+        ]
+    ],
+    "checkver": "github",
+    "autoupdate": {
+        "architecture": {
+            "64bit": {
+                "url": "https://example.com/$version/app-x64.zip"
+            },
+            "arm64": {
+                "url": "https://example.com/$version/app-arm64.zip"
+            }
+        },
+        "hash": {
+            "url": "$url.sha256"
+        }
+    },
+    "custom": true
+}
+"#;
+
+        assert_eq!(format(MANIFEST).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_format_is_idempotent() {
+        const MANIFEST: &str = r#"{"version":"1.0.0","url":["a","b"],"hash":["A","B"]}"#;
+
+        let formatted = format(MANIFEST).unwrap();
+
+        assert_eq!(format(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_keeps_line_endings() {
+        let formatted = format("{\r\n\"version\": \"1.0.0\"\r\n}\r\n").unwrap();
+
+        assert_eq!(formatted, "{\r\n    \"version\": \"1.0.0\"\r\n}\r\n");
+    }
+}
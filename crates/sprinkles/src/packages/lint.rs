@@ -0,0 +1,553 @@
+//! Manifest linting, for bucket maintainers
+//!
+//! These checks go beyond what deserializing a manifest can catch,
+//! and look for problems that would otherwise only show up when installing or updating an app.
+
+use std::path::{Path, PathBuf};
+
+use quork::traits::list::ListVariants;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use strum::Display;
+
+use crate::{
+    config,
+    contexts::ScoopContext,
+    hash::{formats::text, substitutions::SubstitutionMap, HashType},
+    Architecture,
+};
+
+use super::{
+    manifest::TOrArrayOfTs,
+    reference::{ManifestRef, Package},
+    CreateManifest, Manifest, Result,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+/// How serious a lint is
+pub enum Severity {
+    /// The manifest works, but should be improved
+    Warning,
+    /// The manifest is broken
+    Error,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Display)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+/// The rule a lint was raised for
+pub enum Rule {
+    /// The manifest could not be parsed
+    InvalidManifest,
+    /// The number of hashes does not match the number of urls
+    HashCount,
+    /// There are urls, but no hashes
+    MissingHash,
+    /// A hash is not valid for its algorithm
+    InvalidHash,
+    /// A deprecated field is used
+    DeprecatedField,
+    /// There is an autoupdate config, but no checkver config
+    MissingCheckver,
+    /// There is a checkver config, but no autoupdate config
+    MissingAutoupdate,
+    /// A dependency could not be found in any local bucket
+    UnresolvedDependency,
+    /// An autoupdate field uses a variable that sfsu cannot substitute
+    UnknownVariable,
+}
+
+impl Rule {
+    #[must_use]
+    /// Get the severity of lints raised for this rule
+    pub fn severity(self) -> Severity {
+        match self {
+            Rule::MissingHash
+            | Rule::DeprecatedField
+            | Rule::MissingAutoupdate
+            | Rule::UnknownVariable => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+/// A problem found in a manifest
+pub struct Lint {
+    /// The rule that was broken
+    pub rule: Rule,
+    /// How serious the problem is
+    pub severity: Severity,
+    /// A description of the problem
+    pub message: String,
+}
+
+impl Lint {
+    fn new(rule: Rule, message: impl Into<String>) -> Self {
+        Self {
+            rule,
+            severity: rule.severity(),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// The lints found in a single manifest file
+pub struct Report {
+    /// The name of the manifest
+    pub name: String,
+    /// The path to the manifest
+    pub path: PathBuf,
+    /// The problems found in the manifest
+    pub lints: Vec<Lint>,
+}
+
+impl Report {
+    /// Lint the manifest at the given path
+    ///
+    /// Dependencies are resolved against the other manifests in the same directory,
+    /// and then against every local bucket
+    ///
+    /// # Errors
+    /// - Reading the manifest file
+    pub fn lint_file(
+        ctx: &impl ScoopContext<config::Scoop>,
+        path: impl AsRef<Path>,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+
+        let directory = path.parent().unwrap_or(Path::new("."));
+        let resolves = |dependency: &ManifestRef| match dependency {
+            ManifestRef::File(path) => path.exists(),
+            ManifestRef::Url(_) => true,
+            ManifestRef::Name(name) if directory.join(format!("{name}.json")).exists() => true,
+            _ => Package::from_ref(dependency.clone())
+                .list_manifest_paths(ctx)
+                .iter()
+                .any(|path| path.exists()),
+        };
+
+        Ok(Self {
+            name: path
+                .file_stem()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: path.to_path_buf(),
+            lints: lint(&contents, resolves),
+        })
+    }
+
+    #[must_use]
+    /// Check if any of the lints are errors
+    pub fn has_errors(&self) -> bool {
+        self.lints
+            .iter()
+            .any(|lint| lint.severity == Severity::Error)
+    }
+}
+
+/// Lint the contents of a manifest
+///
+/// `resolves` is used to check whether a dependency can be found
+pub fn lint(contents: &str, resolves: impl Fn(&ManifestRef) -> bool) -> Vec<Lint> {
+    let contents = contents.trim_start_matches('\u{feff}');
+
+    let raw: Value = match serde_json::from_str(contents) {
+        Ok(raw) => raw,
+        Err(e) => return vec![Lint::new(Rule::InvalidManifest, e.to_string())],
+    };
+
+    let mut lints = vec![];
+
+    lint_hashes(&raw, &mut lints);
+    lint_deprecated(&raw, &mut lints);
+    if let Some(autoupdate) = raw.get("autoupdate") {
+        let has_matches = has_checkver_regex(&raw);
+        lint_variables(autoupdate, "autoupdate", false, has_matches, &mut lints);
+    }
+
+    match Manifest::from_str(contents.to_string()) {
+        Ok(manifest) => {
+            lint_hash_count(&manifest, &mut lints);
+            lint_autoupdate_pair(&manifest, &mut lints);
+
+            for dependency in manifest.depends() {
+                if !resolves(&dependency) {
+                    lints.push(Lint::new(
+                        Rule::UnresolvedDependency,
+                        format!("Could not find dependency `{dependency}`"),
+                    ));
+                }
+            }
+        }
+        // Invalid hashes also fail to parse, so avoid reporting them twice
+        Err(_) if lints.iter().any(|lint| lint.rule == Rule::InvalidHash) => {}
+        Err(e) => lints.push(Lint::new(Rule::InvalidManifest, e.to_string())),
+    }
+
+    lints
+}
+
+fn arch_name(arch: Architecture) -> &'static str {
+    match arch {
+        Architecture::Arm64 => "arm64",
+        Architecture::X64 => "64bit",
+        Architecture::X86 => "32bit",
+    }
+}
+
+fn lint_hashes(raw: &Value, lints: &mut Vec<Lint>) {
+    let mut hashes = vec![("hash".to_string(), raw.get("hash"))];
+    for arch in Architecture::VARIANTS {
+        let name = arch_name(arch);
+        let hash = raw
+            .get("architecture")
+            .and_then(|architecture| architecture.get(name))
+            .and_then(|config| config.get("hash"));
+
+        hashes.push((format!("architecture.{name}.hash"), hash));
+    }
+
+    for (field, value) in hashes {
+        let values = match value {
+            Some(Value::Array(values)) => values.iter().collect(),
+            Some(value) => vec![value],
+            None => vec![],
+        };
+
+        for value in values {
+            let problem = match value.as_str() {
+                Some(hash) => validate_hash(hash),
+                None => Some(format!("expected a string, found `{value}`")),
+            };
+
+            if let Some(problem) = problem {
+                lints.push(Lint::new(
+                    Rule::InvalidHash,
+                    format!("Invalid hash in `{field}`: {problem}"),
+                ));
+            }
+        }
+    }
+}
+
+/// Check that a hash is valid, in the way Scoop reads it
+///
+/// Unlike [`HashType`] parsing, hashes without a prefix are always treated as sha256
+fn validate_hash(hash: &str) -> Option<String> {
+    let (hash_type, hex) = [HashType::SHA512, HashType::SHA1, HashType::MD5]
+        .into_iter()
+        .find_map(|hash_type| {
+            hash.strip_prefix(&hash_type.to_string())
+                .map(|hex| (hash_type, hex))
+        })
+        .unwrap_or((
+            HashType::SHA256,
+            hash.strip_prefix("sha256:").unwrap_or(hash),
+        ));
+
+    let expected_length = match hash_type {
+        HashType::MD5 => 32,
+        HashType::SHA1 => 40,
+        HashType::SHA256 => 64,
+        HashType::SHA512 => 128,
+    };
+
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(format!("`{hash}` is not a hexadecimal string"))
+    } else if hex.len() != expected_length {
+        let algorithm = match hash_type {
+            HashType::SHA256 => "sha256".to_string(),
+            hash_type => hash_type.to_string().trim_end_matches(':').to_string(),
+        };
+
+        Some(format!(
+            "`{hash}` has {} characters, but {algorithm} hashes have {expected_length}. Hashes without a prefix are treated as sha256",
+            hex.len()
+        ))
+    } else {
+        None
+    }
+}
+
+fn lint_deprecated(raw: &Value, lints: &mut Vec<Lint>) {
+    if raw.get("_comment").is_some() {
+        lints.push(Lint::new(
+            Rule::DeprecatedField,
+            "`_comment` is deprecated. Use `##` instead",
+        ));
+    }
+
+    let mut msi_fields = vec![];
+    if raw.get("msi").is_some() {
+        msi_fields.push("msi".to_string());
+    }
+    for arch in Architecture::VARIANTS {
+        let name = arch_name(arch);
+        if raw
+            .get("architecture")
+            .and_then(|architecture| architecture.get(name))
+            .and_then(|config| config.get("msi"))
+            .is_some()
+        {
+            msi_fields.push(format!("architecture.{name}.msi"));
+        }
+    }
+
+    for field in msi_fields {
+        lints.push(Lint::new(
+            Rule::DeprecatedField,
+            format!("`{field}` is deprecated and ignored by Scoop"),
+        ));
+    }
+}
+
+/// Check if the manifest's checkver uses a regex, whose captures are available to autoupdate as `$match` variables
+///
+/// A string checkver is either a regex or `github`, and the `github` and `sourceforge` checkvers fall back to a default regex
+fn has_checkver_regex(raw: &Value) -> bool {
+    match raw.get("checkver") {
+        Some(Value::String(_)) => true,
+        Some(Value::Object(checkver)) => ["regex", "re", "github", "sourceforge"]
+            .iter()
+            .any(|key| checkver.contains_key(*key)),
+        _ => false,
+    }
+}
+
+fn lint_variables(
+    value: &Value,
+    field: &str,
+    in_hash: bool,
+    has_matches: bool,
+    lints: &mut Vec<Lint>,
+) {
+    match value {
+        Value::String(string) => {
+            let variable_regex = Regex::new(r"\$[a-zA-Z]\w*").expect("valid variable regex");
+
+            for variable in variable_regex.find_iter(string) {
+                let variable = variable.as_str();
+
+                let known = SubstitutionMap::VARIABLES.contains(&variable)
+                    || (in_hash && text::is_template(variable))
+                    || (has_matches
+                        && variable
+                            .strip_prefix("$match")
+                            .is_some_and(|name| !name.is_empty()));
+
+                if !known {
+                    lints.push(Lint::new(
+                        Rule::UnknownVariable,
+                        format!("`{field}` uses `{variable}`, which sfsu cannot substitute"),
+                    ));
+                }
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                lint_variables(value, field, in_hash, has_matches, lints);
+            }
+        }
+        Value::Object(object) => {
+            for (key, value) in object {
+                lint_variables(
+                    value,
+                    &format!("{field}.{key}"),
+                    in_hash || key == "hash",
+                    has_matches,
+                    lints,
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+fn lint_hash_count(manifest: &Manifest, lints: &mut Vec<Lint>) {
+    fn count<T>(field: Option<TOrArrayOfTs<T>>) -> usize {
+        field.map_or(0, |field| field.to_vec().len())
+    }
+
+    let configs = if let Some(architecture) = &manifest.architecture {
+        Architecture::VARIANTS
+            .into_iter()
+            .filter(|arch| architecture[*arch].is_some())
+            .map(|arch| {
+                (
+                    format!("`{}`", arch_name(arch)),
+                    manifest.install_config(arch),
+                )
+            })
+            .collect()
+    } else {
+        vec![("The manifest".to_string(), manifest.install_config.clone())]
+    };
+
+    for (name, config) in configs {
+        let urls = count(config.url);
+        let hashes = count(config.hash);
+
+        if urls == 0 {
+            continue;
+        }
+
+        if hashes == 0 {
+            lints.push(Lint::new(
+                Rule::MissingHash,
+                format!("{name} has {urls} url(s), but no hashes"),
+            ));
+        } else if urls != hashes {
+            lints.push(Lint::new(
+                Rule::HashCount,
+                format!("{name} has {urls} url(s), but {hashes} hash(es)"),
+            ));
+        }
+    }
+}
+
+fn lint_autoupdate_pair(manifest: &Manifest, lints: &mut Vec<Lint>) {
+    match (
+        manifest.install_config.checkver.is_some(),
+        manifest.autoupdate.is_some(),
+    ) {
+        (false, true) => lints.push(Lint::new(
+            Rule::MissingCheckver,
+            "`autoupdate` is set, but there is no `checkver` to find new versions with",
+        )),
+        (true, false) => lints.push(Lint::new(
+            Rule::MissingAutoupdate,
+            "`checkver` is set, but there is no `autoupdate` to update the manifest with",
+        )),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use crate::{hash::url_ext::UrlExt, version::Version};
+
+    use super::*;
+
+    fn rules(contents: &str) -> Vec<Rule> {
+        lint(contents, |_| true)
+            .into_iter()
+            .map(|lint| lint.rule)
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_manifest() {
+        const MANIFEST: &str = r#"{"version":"1.0.0","url":"https://example.com/app-1.0.0.zip","hash":"b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9","checkver":"github","autoupdate":{"url":"https://example.com/app-$version.zip","hash":{"url":"$url.sha256","regex":"$sha256"}}}"#;
+
+        assert_eq!(lint(MANIFEST, |_| true), vec![]);
+    }
+
+    #[test]
+    fn test_hash_count() {
+        const MANIFEST: &str = r#"{"version":"1.0.0","architecture":{"64bit":{"url":["https://example.com/a.zip","https://example.com/b.zip"],"hash":"b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"},"32bit":{"url":"https://example.com/a.zip"}}}"#;
+
+        assert_eq!(rules(MANIFEST), vec![Rule::HashCount, Rule::MissingHash]);
+    }
+
+    #[test]
+    fn test_invalid_hash() {
+        const MANIFEST: &str = r#"{"version":"1.0.0","url":"https://example.com/a.zip","hash":"2aae6c35c94fcfb415dbe95f408b9ce91ee846ed"}"#;
+
+        assert_eq!(rules(MANIFEST), vec![Rule::InvalidHash]);
+
+        assert!(validate_hash("sha1:2aae6c35c94fcfb415dbe95f408b9ce91ee846ed").is_none());
+        assert!(validate_hash("md5:5eb63bbbe01eeed093cb22bb8f5acdc3").is_none());
+        assert!(validate_hash("md5:not a hash").is_some());
+    }
+
+    #[test]
+    fn test_deprecated_fields() {
+        const MANIFEST: &str =
+            r#"{"version":"1.0.0","_comment":"old","architecture":{"64bit":{"msi":"app.msi"}}}"#;
+
+        assert_eq!(
+            rules(MANIFEST),
+            vec![Rule::DeprecatedField, Rule::DeprecatedField]
+        );
+    }
+
+    #[test]
+    fn test_autoupdate_pair() {
+        assert_eq!(
+            rules(r#"{"version":"1.0.0","autoupdate":{"url":"https://example.com/$version.zip"}}"#),
+            vec![Rule::MissingCheckver]
+        );
+        assert_eq!(
+            rules(r#"{"version":"1.0.0","checkver":"github"}"#),
+            vec![Rule::MissingAutoupdate]
+        );
+    }
+
+    #[test]
+    fn test_unresolved_dependency() {
+        const MANIFEST: &str = r#"{"version":"1.0.0","depends":["main/7zip","missing"]}"#;
+
+        let lints = lint(MANIFEST, |dependency| dependency.to_string() == "main/7zip");
+
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].rule, Rule::UnresolvedDependency);
+        assert!(lints[0].message.contains("`missing`"));
+    }
+
+    #[test]
+    fn test_unknown_variables() {
+        const MANIFEST: &str = r#"{"version":"1.0.0","checkver":{"url":"https://example.com/version.json","jsonpath":"$.version"},"autoupdate":{"url":"https://example.com/$match1/$version.zip","extract_dir":"app-$sha256","hash":{"url":"$baseurl/sums.txt","regex":"$sha256\\s+$basename"}}}"#;
+
+        let lints = lint(MANIFEST, |_| true);
+
+        assert_eq!(
+            lints.iter().map(|lint| lint.rule).collect::<Vec<_>>(),
+            vec![Rule::UnknownVariable, Rule::UnknownVariable]
+        );
+        assert!(lints[0].message.contains("`$match1`"));
+        assert!(lints[1].message.contains("`$sha256`"));
+    }
+
+    #[test]
+    fn test_checkver_match_variables() {
+        for checkver in [
+            r#""github""#,
+            r#""v([\\d.]+)""#,
+            r#"{"url":"https://example.com","regex":"(?<head>\\d+)\\.(\\d+)"}"#,
+            r#"{"github":"https://github.com/winpax/sfsu"}"#,
+            r#"{"sourceforge":"sevenzip"}"#,
+        ] {
+            let manifest = format!(
+                r#"{{"version":"1.0.0","checkver":{checkver},"autoupdate":{{"url":"https://example.com/$match1/$matchHead/$version.zip"}}}}"#
+            );
+
+            assert!(lint(&manifest, |_| true).is_empty(), "{checkver}");
+        }
+    }
+
+    #[test]
+    fn test_known_variables() {
+        let version = Version::new("1.2.3-beta+4");
+        let url = Url::parse("https://example.com/downloads/app-1.2.3.zip").unwrap();
+
+        for variable in version.submap().keys().chain(url.submap().keys()) {
+            assert!(
+                SubstitutionMap::VARIABLES.contains(&variable.as_str()),
+                "{variable} is missing from the known variables"
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        assert_eq!(rules("{"), vec![Rule::InvalidManifest]);
+    }
+}
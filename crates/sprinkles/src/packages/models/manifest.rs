@@ -98,6 +98,16 @@ impl std::ops::Index<Architecture> for ManifestArchitecture {
     }
 }
 
+impl std::ops::IndexMut<Architecture> for ManifestArchitecture {
+    fn index_mut(&mut self, index: Architecture) -> &mut Self::Output {
+        match index {
+            Architecture::Arm64 => &mut self.arm64,
+            Architecture::X64 => &mut self.x64,
+            Architecture::X86 => &mut self.x86,
+        }
+    }
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
 /// The install configuration
@@ -257,6 +267,14 @@ pub struct Psmodule {
     pub name: Option<String>,
 }
 
+impl From<AutoupdatePsmodule> for Psmodule {
+    fn from(psmodule: AutoupdatePsmodule) -> Self {
+        Self {
+            name: psmodule.name,
+        }
+    }
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Suggest {}
@@ -422,6 +440,15 @@ pub enum AutoupdateLicense {
     String(String),
 }
 
+impl From<AutoupdateLicense> for PackageLicense {
+    fn from(license: AutoupdateLicense) -> Self {
+        match license {
+            AutoupdateLicense::License(license) => Self::License(license),
+            AutoupdateLicense::String(license) => Self::String(license),
+        }
+    }
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(untagged)]
@@ -480,6 +507,8 @@ pub enum HashMode {
     Extract,
     #[serde(rename = "fosshub")]
     Fosshub,
+    #[serde(rename = "headers")]
+    Headers,
     #[serde(rename = "json")]
     Json,
     #[serde(rename = "metalink")]
@@ -13,22 +13,45 @@ pub struct Info {
     pub current: String,
     /// The available version
     pub available: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The bucket offering the available version
+    ///
+    /// Only set when searching every bucket for the newest version
+    pub bucket: Option<String>,
 }
 
 impl Info {
     #[must_use]
     /// Get the outdated info from a local and remote manifest combo
     ///
-    /// Returns [`None`] if they have the same version
+    /// Returns [`None`] if they have equivalent versions
     pub fn from_manifests(local: &Manifest, remote: &Manifest) -> Option<Self> {
-        if local.version == remote.version {
+        if local.version.compare(&remote.version).is_eq() {
             None
         } else {
             Some(Info {
                 name: remote.name.clone(),
                 current: local.version.to_string(),
                 available: remote.version.to_string(),
+                bucket: None,
             })
         }
     }
+
+    #[must_use]
+    /// Get the outdated info if the remote manifest has a newer version than the local manifest
+    ///
+    /// The bucket of the remote manifest is reported as offering the newer version
+    pub fn from_newer_manifest(local: &Manifest, remote: &Manifest) -> Option<Self> {
+        if remote.version.compare(&local.version).is_gt() {
+            Some(Info {
+                name: remote.name.clone(),
+                current: local.version.to_string(),
+                available: remote.version.to_string(),
+                bucket: Some(remote.bucket.clone()),
+            })
+        } else {
+            None
+        }
+    }
 }
@@ -6,6 +6,7 @@ use serde::Serialize;
 
 use crate::config;
 use crate::contexts::ScoopContext;
+use crate::version::Version;
 use crate::{buckets::Bucket, packages::reference::ManifestRef};
 
 use crate::packages::{reference, Manifest, Result};
@@ -23,9 +24,22 @@ pub struct Info {
     pub missing_dependencies: Vec<reference::Package>,
     /// Additional information
     pub info: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The bucket offering the available version
+    ///
+    /// Only set when searching every bucket for the newest version
+    pub bucket: Option<String>,
 }
 
 impl Info {
+    #[must_use]
+    /// Check if the available version is different from the current version
+    pub fn is_outdated(&self) -> bool {
+        Version::new(&self.current)
+            .compare(&Version::new(&self.available))
+            .is_ne()
+    }
+
     /// Parse [`Info`] from a local manifest
     ///
     /// # Errors
@@ -38,6 +52,24 @@ impl Info {
         ctx: &impl ScoopContext<config::Scoop>,
         local_manifest: &Manifest,
         bucket: &Bucket,
+    ) -> Result<Self> {
+        debug!("Local manifest name: {}", local_manifest.name);
+        let remote_manifest = bucket.get_manifest(&local_manifest.name)?;
+
+        Self::from_remote_manifest(ctx, local_manifest, &remote_manifest)
+    }
+
+    /// Parse [`Info`] from a local manifest and the remote manifest to compare it against
+    ///
+    /// # Errors
+    /// - If the install manifest is missing
+    ///
+    /// # Panics
+    /// - Invalid package reference name
+    pub fn from_remote_manifest(
+        ctx: &impl ScoopContext<config::Scoop>,
+        local_manifest: &Manifest,
+        remote_manifest: &Manifest,
     ) -> Result<Self> {
         let failed = {
             let installed = ctx.app_installed(&local_manifest.name)?;
@@ -47,9 +79,6 @@ impl Info {
             !app_path.exists() && installed
         };
 
-        debug!("Local manifest name: {}", local_manifest.name);
-        let remote_manifest = bucket.get_manifest(&local_manifest.name)?;
-
         let install_manifest = local_manifest.install_manifest(ctx)?;
 
         let held = install_manifest.hold.unwrap_or_default();
@@ -82,6 +111,7 @@ impl Info {
             available: remote_manifest.version.to_string(),
             missing_dependencies,
             info: (!info.is_empty()).then_some(info),
+            bucket: None,
         })
     }
 }
@@ -11,7 +11,7 @@ use crate::{
     buckets::{self, Bucket},
     config,
     contexts::ScoopContext,
-    let_chain,
+    let_chain, offline,
     requests::Client,
 };
 
@@ -43,6 +43,8 @@ pub enum Error {
     Serde(#[from] serde_json::Error),
     #[error("Buckets error: {0}")]
     Buckets(#[from] buckets::Error),
+    #[error("Cannot download manifests from urls while offline")]
+    Offline,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -189,7 +191,11 @@ impl Package {
                 ManifestRef::File(path) => Manifest::from_path(path)?,
                 #[cfg(feature = "manifest-hashes")]
                 ManifestRef::Url(url) => {
-                    let manifest_string = Client::asynchronous()
+                    if offline::enabled() {
+                        return Err(Error::Offline);
+                    }
+
+                    let manifest_string = AsyncClient::with_config(ctx.config())
                         .get(url.to_string())
                         .send()
                         .await?
@@ -6,9 +6,9 @@
 //! You are welcome to do so, but be aware that the API may change without warning, and it will likely not meet your requirements.
 
 use derive_more::Deref;
-use reqwest::header::HeaderMap;
+use reqwest::header::{self, HeaderMap, HeaderValue};
 
-use crate::config;
+use crate::{config, proxy::Proxy};
 
 #[must_use]
 #[deprecated(note = "Use `USER_AGENT` instead")]
@@ -37,8 +37,6 @@ pub const USER_AGENT: &str = {
 /// # Panics
 /// - Invalid headers
 pub fn default_headers() -> HeaderMap {
-    use reqwest::header::{self, HeaderValue};
-
     let mut headers = HeaderMap::new();
 
     headers.insert(header::ACCEPT, HeaderValue::from_static("*/*"));
@@ -47,6 +45,87 @@ pub fn default_headers() -> HeaderMap {
     headers
 }
 
+/// Hosts that are sent the GitHub token, if one is configured
+pub const GITHUB_HOSTS: &[&str] = &[
+    "github.com",
+    "api.github.com",
+    "objects.githubusercontent.com",
+];
+
+/// The environment variable that overrides the GitHub token in the Scoop config
+pub const GITHUB_TOKEN_ENV: &str = "SCOOP_GH_TOKEN";
+
+#[must_use]
+/// Get the GitHub token from the [`GITHUB_TOKEN_ENV`] environment variable, or the Scoop config
+pub fn github_token(config: Option<&config::Scoop>) -> Option<String> {
+    std::env::var(GITHUB_TOKEN_ENV)
+        .ok()
+        .filter(|token| !token.is_empty())
+        .or_else(|| config.and_then(|config| config.gh_token.clone()))
+        .filter(|token| !token.is_empty())
+}
+
+#[must_use]
+/// Check if the url is hosted on GitHub, and should be sent the GitHub token
+///
+/// Pre-signed urls are never sent the token, as they carry their own credentials and reject any others
+pub fn is_github_url(url: &reqwest::Url) -> bool {
+    url.scheme() == "https"
+        && url
+            .host_str()
+            .is_some_and(|host| GITHUB_HOSTS.contains(&host))
+        && !is_presigned(url)
+}
+
+fn is_presigned(url: &reqwest::Url) -> bool {
+    url.query_pairs()
+        .any(|(key, _)| key.eq_ignore_ascii_case("X-Amz-Signature"))
+}
+
+#[must_use]
+/// Construct the headers to authenticate a request to the url
+///
+/// Requests to [`GITHUB_HOSTS`] are authenticated with the GitHub token, if one is provided
+pub fn auth_headers(url: &str, github_token: Option<&str>) -> HeaderMap {
+    auth_headers_with_api(url, github_token, None)
+}
+
+#[must_use]
+/// Construct the headers to authenticate a request to the url, with a custom GitHub API url
+///
+/// Like [`auth_headers`], but requests to the same scheme, host and port as the GitHub API url are also authenticated,
+/// so a GitHub Enterprise server is sent the token
+pub fn auth_headers_with_api(
+    url: &str,
+    github_token: Option<&str>,
+    github_api_url: Option<&str>,
+) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+
+    let Some(token) = github_token else {
+        return headers;
+    };
+
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return headers;
+    };
+
+    let is_api_url = github_api_url
+        .and_then(|api_url| reqwest::Url::parse(api_url).ok())
+        .is_some_and(|api_url| api_url.origin() == url.origin() && !is_presigned(&url));
+
+    if !is_github_url(&url) && !is_api_url {
+        return headers;
+    }
+
+    if let Ok(mut value) = HeaderValue::from_str(&format!("Bearer {token}")) {
+        value.set_sensitive(true);
+        headers.insert(header::AUTHORIZATION, value);
+    }
+
+    headers
+}
+
 pub trait ClientLike<T>
 where
     Self: Default,
@@ -80,55 +159,148 @@ impl Client {
 
 #[derive(Debug, Clone, Deref)]
 /// A blocking client with sane defaults for SFSU
-pub struct BlockingClient(reqwest::blocking::Client);
+pub struct BlockingClient {
+    #[deref]
+    client: reqwest::blocking::Client,
+    github_token: Option<String>,
+}
+
+impl BlockingClient {
+    #[must_use]
+    /// Construct a client with the proxy and GitHub token from the config
+    ///
+    /// If the proxy is invalid, the client connects directly
+    ///
+    /// # Panics
+    /// - The TLS backend could not be initialized
+    pub fn with_config(config: &config::Scoop) -> Self {
+        let client = Proxy::from_config(config).apply_blocking(
+            reqwest::blocking::Client::builder().default_headers(default_headers()),
+        );
+
+        Self {
+            client: client.build().unwrap(),
+            github_token: github_token(Some(config)),
+        }
+    }
+
+    /// Start a `GET` request to the url, authenticated for its host
+    pub fn get(&self, url: impl AsRef<str>) -> reqwest::blocking::RequestBuilder {
+        let url = url.as_ref();
+
+        self.client
+            .get(url)
+            .headers(auth_headers(url, self.github_token.as_deref()))
+    }
+
+    /// Start a `HEAD` request to the url, authenticated for its host
+    pub fn head(&self, url: impl AsRef<str>) -> reqwest::blocking::RequestBuilder {
+        let url = url.as_ref();
+
+        self.client
+            .head(url)
+            .headers(auth_headers(url, self.github_token.as_deref()))
+    }
+}
 
 impl ClientLike<reqwest::blocking::Client> for BlockingClient {
     fn client(&self) -> &reqwest::blocking::Client {
-        &self.0
+        &self.client
+    }
+}
+
+impl From<reqwest::blocking::Client> for BlockingClient {
+    /// Wrap an existing client, which will not authenticate any requests
+    fn from(client: reqwest::blocking::Client) -> Self {
+        Self {
+            client,
+            github_token: None,
+        }
     }
 }
 
 impl Default for BlockingClient {
+    /// Construct a client with the config shared by this process
+    ///
+    /// Prefer [`BlockingClient::with_config`] when a context's config is available
     fn default() -> Self {
-        let client = reqwest::blocking::Client::builder().default_headers(default_headers());
-
-        let client = if let Some(proxy) = config::Scoop::load().expect("scoop config").proxy {
-            client.proxy(proxy.try_into().expect("valid reqwest proxy"))
-        } else {
-            client
-        };
-
-        Self(client.build().unwrap())
+        Self::with_config(config::Scoop::shared())
     }
 }
 
 #[derive(Debug, Clone, Deref)]
 /// An async client with sane defaults for SFSU
-pub struct AsyncClient(reqwest::Client);
+pub struct AsyncClient {
+    #[deref]
+    client: reqwest::Client,
+    github_token: Option<String>,
+}
+
+impl AsyncClient {
+    #[must_use]
+    /// Construct a client with the proxy and GitHub token from the config
+    ///
+    /// If the proxy is invalid, the client connects directly
+    ///
+    /// # Panics
+    /// - The TLS backend could not be initialized
+    pub fn with_config(config: &config::Scoop) -> Self {
+        let client = Proxy::from_config(config)
+            .apply(reqwest::Client::builder().default_headers(default_headers()));
+
+        Self {
+            client: client.build().unwrap(),
+            github_token: github_token(Some(config)),
+        }
+    }
+
+    /// Start a `GET` request to the url, authenticated for its host
+    pub fn get(&self, url: impl AsRef<str>) -> reqwest::RequestBuilder {
+        let url = url.as_ref();
+
+        self.client
+            .get(url)
+            .headers(auth_headers(url, self.github_token.as_deref()))
+    }
+
+    /// Start a `HEAD` request to the url, authenticated for its host
+    pub fn head(&self, url: impl AsRef<str>) -> reqwest::RequestBuilder {
+        let url = url.as_ref();
+
+        self.client
+            .head(url)
+            .headers(auth_headers(url, self.github_token.as_deref()))
+    }
+}
 
 impl ClientLike<reqwest::Client> for AsyncClient {
     fn client(&self) -> &reqwest::Client {
-        &self.0
+        &self.client
+    }
+}
+
+impl From<reqwest::Client> for AsyncClient {
+    /// Wrap an existing client, which will not authenticate any requests
+    fn from(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            github_token: None,
+        }
     }
 }
 
 impl Default for AsyncClient {
+    /// Construct a client with the config shared by this process
+    ///
+    /// Prefer [`AsyncClient::with_config`] when a context's config is available
     fn default() -> Self {
-        let client = reqwest::Client::builder().default_headers(default_headers());
-
-        let client = if let Some(proxy) = config::Scoop::load().expect("scoop config").proxy {
-            client.proxy(proxy.try_into().expect("valid reqwest proxy"))
-        } else {
-            client
-        };
-
-        Self(client.build().unwrap())
+        Self::with_config(config::Scoop::shared())
     }
 }
 
 impl ClientLike<reqwest::Client> for reqwest::Client {
     fn new() -> Self {
-        AsyncClient::new().0
+        AsyncClient::new().client
     }
 
     fn client(&self) -> &reqwest::Client {
@@ -138,10 +310,91 @@ impl ClientLike<reqwest::Client> for reqwest::Client {
 
 impl ClientLike<reqwest::blocking::Client> for reqwest::blocking::Client {
     fn new() -> Self {
-        BlockingClient::new().0
+        BlockingClient::new().client
     }
 
     fn client(&self) -> &reqwest::blocking::Client {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_headers() {
+        let token = Some("ghp_token");
+
+        for url in [
+            "https://github.com/winpax/sfsu/releases/download/v1.0.0/sfsu.exe",
+            "https://api.github.com/repos/winpax/sfsu/releases/assets/1",
+            "https://objects.githubusercontent.com/github-production-release-asset/1",
+        ] {
+            assert_eq!(
+                auth_headers(url, token).get(header::AUTHORIZATION).unwrap(),
+                "Bearer ghp_token"
+            );
+        }
+
+        for url in [
+            "https://example.com/sfsu.exe",
+            "https://github.com.example.com/sfsu.exe",
+            "http://github.com/winpax/sfsu",
+            "https://objects.githubusercontent.com/asset?X-Amz-Signature=abc",
+        ] {
+            assert!(auth_headers(url, token).is_empty());
+        }
+
+        assert!(auth_headers("https://github.com/winpax/sfsu", None).is_empty());
+    }
+
+    #[test]
+    fn test_auth_headers_with_api() {
+        let token = Some("ghp_token");
+        let api_url = Some("https://github.example.com/api/v3");
+
+        for url in [
+            "https://github.example.com/api/v3/repos/winpax/sfsu/releases/latest",
+            "https://github.example.com/winpax/sfsu/releases/download/v1.0.0/sfsu.exe",
+            "https://github.com/winpax/sfsu/releases/download/v1.0.0/sfsu.exe",
+        ] {
+            assert_eq!(
+                auth_headers_with_api(url, token, api_url)
+                    .get(header::AUTHORIZATION)
+                    .unwrap(),
+                "Bearer ghp_token"
+            );
+        }
+
+        for url in [
+            "http://github.example.com/api/v3/repos/winpax/sfsu/releases/latest",
+            "https://github.example.com:8443/api/v3",
+            "https://example.com/sfsu.exe",
+            "https://github.example.com/asset?X-Amz-Signature=abc",
+        ] {
+            assert!(auth_headers_with_api(url, token, api_url).is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_proxy_connects_directly() {
+        use crate::test_server::{Response, TestServer};
+
+        let server = TestServer::start(|_| Response::ok("direct"));
+
+        let mut config = config::Scoop::default();
+        config.proxy = Some(Proxy::new(None, None, "invalid host".into(), 8080));
+
+        let body = AsyncClient::with_config(&config)
+            .get(server.url("/"))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+
+        assert_eq!(body, "direct");
+    }
+}
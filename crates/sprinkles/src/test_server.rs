@@ -0,0 +1,163 @@
+//! A minimal HTTP server to test requests against, without relying on the network
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::Arc,
+};
+
+use parking_lot::Mutex;
+
+#[derive(Debug, Clone)]
+/// A request received by the server
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// Header names are lowercase
+    pub headers: HashMap<String, String>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A response to send back to the client
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: 200,
+            headers: vec![],
+            body: body.into(),
+        }
+    }
+
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            headers: vec![],
+            body: vec![],
+        }
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+}
+
+/// A server running on a background thread, which lives for the rest of the test
+pub struct TestServer {
+    address: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl TestServer {
+    pub fn start(handler: impl Fn(&Request) -> Response + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+
+        let handler = Arc::new(handler);
+        let received = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let handler = handler.clone();
+                let received = received.clone();
+
+                std::thread::spawn(move || Self::handle(stream, handler.as_ref(), &received));
+            }
+        });
+
+        Self { address, requests }
+    }
+
+    /// Get the url for the given path on the server
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.address)
+    }
+
+    /// Get every request the server has received so far
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().clone()
+    }
+
+    fn handle(
+        mut stream: TcpStream,
+        handler: &(impl Fn(&Request) -> Response + ?Sized),
+        received: &Mutex<Vec<Request>>,
+    ) -> Option<()> {
+        let mut reader = BufReader::new(stream.try_clone().ok()?);
+
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).ok()?;
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next()?.to_string();
+        let path = parts.next()?.to_string();
+
+        let mut headers = HashMap::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).ok()?;
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = line.split_once(':') {
+                headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+            }
+        }
+
+        if let Some(length) = headers
+            .get("content-length")
+            .and_then(|length| length.parse::<u64>().ok())
+        {
+            std::io::copy(&mut reader.by_ref().take(length), &mut std::io::sink()).ok()?;
+        }
+
+        let request = Request {
+            method,
+            path,
+            headers,
+        };
+        let response = handler(&request);
+        received.lock().push(request.clone());
+
+        let mut head = format!("HTTP/1.1 {} Test\r\n", response.status);
+        for (name, value) in &response.headers {
+            head += &format!("{name}: {value}\r\n");
+        }
+        if !response
+            .headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        {
+            head += &format!("Content-Length: {}\r\n", response.body.len());
+        }
+        head += "Connection: close\r\n\r\n";
+
+        stream.write_all(head.as_bytes()).ok()?;
+        if request.method != "HEAD" {
+            // The client may hang up early, e.g. when it only wanted part of the body
+            _ = stream.write_all(&response.body);
+        }
+        _ = stream.flush();
+
+        Some(())
+    }
+}
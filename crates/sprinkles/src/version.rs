@@ -2,6 +2,7 @@
 
 use std::{
     borrow::Cow,
+    cmp::Ordering,
     fmt::{Display, Formatter},
     num::ParseIntError,
 };
@@ -41,6 +42,21 @@ impl Version {
         &self.0
     }
 
+    #[must_use]
+    /// Compare two versions the same way Scoop's `Compare-Version` does
+    ///
+    /// Versions are split on dashes, dots and underscores, and on the boundaries of letters.
+    /// Numeric segments are compared as numbers, and any other pair of segments is compared as case-insensitive strings.
+    /// This includes a number against a word, so `1.2.3` is older than `1.2.rc`.
+    /// A trailing `alpha`, `beta`, `rc` or `pre` segment marks a pre-release, which is older than the plain version,
+    /// and `+` is treated as a post-release separator.
+    ///
+    /// Unlike [`Ord::cmp`], this will return [`Ordering::Equal`] for versions that are written differently but are otherwise equivalent,
+    /// such as `1.0` and `1.00`, or any two `nightly` versions.
+    pub fn compare(&self, other: &Self) -> Ordering {
+        compare_parts(&self.0.replace('+', "-"), &other.0.replace('+', "-"), '-')
+    }
+
     #[must_use]
     /// Get the version string with dots instead of separators
     pub fn dot_version(&self) -> Cow<'_, str> {
@@ -143,6 +159,121 @@ impl Version {
     }
 }
 
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    /// Compare versions using [`Version::compare`]
+    ///
+    /// Equivalent versions that are written differently are ordered by their string representation,
+    /// so that the ordering stays consistent with [`Eq`]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.compare(other).then_with(|| self.0.cmp(&other.0))
+    }
+}
+
+fn compare_parts(a: &str, b: &str, delimiter: char) -> Ordering {
+    if a.eq_ignore_ascii_case(b) {
+        return Ordering::Equal;
+    }
+
+    let a_parts = split_version(a, delimiter);
+    let b_parts = split_version(b, delimiter);
+
+    let is_nightly = |parts: &[String]| {
+        parts
+            .first()
+            .is_some_and(|part| part.eq_ignore_ascii_case("nightly"))
+    };
+
+    // Nightly versions are always equal
+    if is_nightly(&a_parts) && is_nightly(&b_parts) {
+        return Ordering::Equal;
+    }
+
+    for i in 0..a_parts.len().max(b_parts.len()) {
+        let ordering = match (a_parts.get(i), b_parts.get(i)) {
+            // `1.1-beta` is older than `1.1`, which is older than `1.1-1`
+            (None, Some(b_part)) => {
+                return if is_pre_release(b_part) {
+                    Ordering::Greater
+                } else {
+                    Ordering::Less
+                };
+            }
+            (Some(a_part), None) => {
+                return if is_pre_release(a_part) {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                };
+            }
+            (Some(a_part), Some(b_part)) if a_part.contains('.') || b_part.contains('.') => {
+                compare_parts(a_part, b_part, '.')
+            }
+            (Some(a_part), Some(b_part)) if a_part.contains('_') || b_part.contains('_') => {
+                compare_parts(a_part, b_part, '_')
+            }
+            (Some(a_part), Some(b_part)) => match (is_numeric(a_part), is_numeric(b_part)) {
+                (true, true) => compare_numeric(a_part, b_part),
+                // Like Scoop, a number against a word is compared as strings
+                _ => a_part.to_lowercase().cmp(&b_part.to_lowercase()),
+            },
+            (None, None) => unreachable!("index is within the bounds of at least one version"),
+        };
+
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+
+    Ordering::Equal
+}
+
+/// Split the version on the delimiter, and on the boundaries of each run of letters
+fn split_version(version: &str, delimiter: char) -> Vec<String> {
+    let mut spaced = String::with_capacity(version.len());
+    let mut in_letters = false;
+
+    for c in version.chars() {
+        let is_letter = c.is_ascii_alphabetic();
+        if is_letter != in_letters {
+            spaced.push(delimiter);
+            in_letters = is_letter;
+        }
+        spaced.push(c);
+    }
+
+    spaced
+        .split(delimiter)
+        .filter(|part| !part.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn is_pre_release(part: &str) -> bool {
+    const PRE_RELEASE_TAGS: [&str; 4] = ["alpha", "beta", "rc", "pre"];
+
+    let part = part.to_lowercase();
+
+    PRE_RELEASE_TAGS.iter().any(|tag| part.contains(tag))
+}
+
+fn is_numeric(part: &str) -> bool {
+    !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Compare two strings of digits numerically, without being limited by the size of an integer type
+fn compare_numeric(a: &str, b: &str) -> Ordering {
+    let a = a.trim_start_matches('0');
+    let b = b.trim_start_matches('0');
+
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
 impl Display for Version {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -221,6 +352,8 @@ impl Display for ParsedVersion {
 
 #[cfg(test)]
 mod tests {
+    use std::cmp::Ordering;
+
     use semver::Version;
 
     #[test]
@@ -238,4 +371,91 @@ mod tests {
 
         assert_eq!(v1, Version::new(1, 2, 3));
     }
+
+    #[test]
+    fn test_compare_versions() {
+        use Ordering::{Equal, Greater, Less};
+
+        const CASES: &[(&str, &str, Ordering)] = &[
+            // Numeric segments
+            ("0.1", "0.2", Less),
+            ("0.1", "0.1.1", Less),
+            ("1.8.9", "1.8.10", Less),
+            ("1.8.5", "1.9.0", Less),
+            ("3.2.1", "3.10", Less),
+            ("1.2.3.4", "1.2.3", Greater),
+            ("10.0", "9.9.9", Greater),
+            ("99999999999999999999.1", "99999999999999999999.2", Less),
+            // Equal versions
+            ("12.0", "12.0", Equal),
+            ("1.0", "1.00", Equal),
+            ("7.0.4-9", "7.0.4-9", Equal),
+            ("1.0-Beta", "1.0-beta", Equal),
+            // Pre-release tags
+            ("1.0-alpha", "1.0", Less),
+            ("1.0-alpha", "1.0-beta", Less),
+            ("1.0-beta", "1.0-rc", Less),
+            ("1.0-pre", "1.0", Less),
+            ("1.0-rc", "1.0", Less),
+            ("1.0.1-alpha", "1.0.1", Less),
+            ("1.0beta", "1.0", Less),
+            ("1.0-beta.2", "1.0-beta.10", Less),
+            ("24.05-beta", "24.05", Less),
+            ("24.05-beta", "24.06-beta", Less),
+            // Letters
+            ("1.0a", "1.0b", Less),
+            ("1.0b", "1.0a", Greater),
+            ("1.0", "1.0a", Less),
+            ("2.44.0.windows.1", "2.44.0.windows.2", Less),
+            ("2.44.0.windows.1", "2.45.0.windows.1", Less),
+            // Numbers against words are compared as strings
+            ("1.2.3", "1.2.rc", Less),
+            ("1.2.rc", "1.2.3", Greater),
+            ("1.2.RC", "1.2.3", Greater),
+            // Dashes and underscores
+            ("7.0.4-9", "7.0.4-10", Less),
+            ("7.0.4-9", "7.0.4-8", Greater),
+            ("2019-01-01", "2019-01-02", Less),
+            ("2018-01-01", "2019-01-01", Less),
+            ("1_2_3", "1_2_10", Less),
+            ("1.0.0", "1.0.0-1", Less),
+            // Post-release separator
+            ("1.0.0+1", "1.0.0+2", Less),
+            ("1.0.0+1", "1.0.0", Greater),
+            // Nightly versions
+            ("nightly-20190801", "nightly", Equal),
+            ("nightly-20190801", "nightly-20200801", Equal),
+            // Empty versions
+            ("7.0.4-9", "", Greater),
+            ("", "", Equal),
+        ];
+
+        for (a, b, expected) in CASES {
+            let a = super::Version::new(*a);
+            let b = super::Version::new(*b);
+
+            assert_eq!(a.compare(&b), *expected, "comparing {a} to {b}");
+            assert_eq!(
+                b.compare(&a),
+                expected.reverse(),
+                "comparing {b} to {a} (reversed)"
+            );
+        }
+    }
+
+    #[test]
+    fn test_version_ord() {
+        let mut versions = ["1.10", "1.2-beta", "1.00", "1.0", "1.2"]
+            .map(super::Version::new)
+            .to_vec();
+        versions.sort();
+
+        assert_eq!(
+            versions
+                .iter()
+                .map(super::Version::as_str)
+                .collect::<Vec<_>>(),
+            ["1.0", "1.00", "1.2-beta", "1.2", "1.10"]
+        );
+    }
 }
@@ -0,0 +1,100 @@
+//! Offline mode applies to the whole process, so it is tested in its own test binary
+//! to avoid breaking the tests that use the network
+
+#![cfg(feature = "manifest-hashes")]
+
+use sprinkles::{
+    cache::{self, Downloader, Handle},
+    checkver::{self, Checker},
+    contexts::{Fixture, ScoopContext},
+    git,
+    hash::{self, Hash, HashType},
+    offline,
+    packages::{CreateManifest, Manifest},
+    requests::AsyncClient,
+    Architecture,
+};
+
+#[allow(dead_code)]
+#[path = "../src/test_server.rs"]
+mod test_server;
+
+use test_server::{Response, TestServer};
+
+#[tokio::test]
+async fn test_offline_network_paths() {
+    let server = TestServer::start(|_| Response::ok("1.0.0"));
+    let dir = tempfile::tempdir().unwrap();
+    let ctx = Fixture::builder(dir.path()).build().unwrap();
+
+    let manifest = Manifest::from_str(format!(
+        r#"{{
+            "version": "1.0.0",
+            "url": "{url}",
+            "checkver": {{ "url": "{url}", "regex": "([\\d.]+)" }},
+            "autoupdate": {{ "url": "{url}", "hash": {{ "url": "{url}" }} }}
+        }}"#,
+        url = server.url("/app.zip")
+    ))
+    .unwrap();
+
+    // A cached file that does not match the manifest hash
+    let handle = Handle::new(
+        ctx.cache_path(),
+        "app#1.0.0#app.zip",
+        HashType::SHA256,
+        server.url("/app.zip"),
+        Hash::from_hex(&[0; 32]),
+    )
+    .unwrap();
+    let cache_path = ctx.cache_path().join(&handle.file_name);
+    std::fs::write(&cache_path, "corrupted").unwrap();
+
+    // A file that is not cached at all
+    let missing = Handle::new(
+        ctx.cache_path(),
+        "app#1.0.0#missing.zip",
+        HashType::SHA256,
+        server.url("/missing.zip"),
+        Hash::from_hex(&[0; 32]),
+    )
+    .unwrap();
+
+    offline::set(true);
+
+    assert!(matches!(
+        Checker::new().check(&manifest).await,
+        Err(checkver::Error::Offline)
+    ));
+
+    assert!(matches!(
+        git::clone::clone(
+            &server.url("/repo.git"),
+            dir.path().join("buckets").join("repo"),
+            git::clone::progress::Discard,
+            ctx.config(),
+        ),
+        Err(git::clone::Error::Offline)
+    ));
+
+    assert!(matches!(
+        Hash::get_for_app(&ctx, &manifest, Architecture::ARCH).await,
+        Err(hash::Error::Offline)
+    ));
+
+    assert!(matches!(
+        Downloader::new::<AsyncClient>(missing, None).await,
+        Err(cache::Error::Offline)
+    ));
+
+    let downloader = Downloader::new::<AsyncClient>(handle, None).await.unwrap();
+    assert!(matches!(
+        downloader.download().await,
+        Err(cache::Error::Offline)
+    ));
+    assert!(cache_path.exists());
+
+    offline::set(false);
+
+    assert!(server.requests().is_empty());
+}
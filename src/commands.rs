@@ -1,7 +1,12 @@
+#[cfg(feature = "download")]
+pub mod autoupdate;
 pub mod bucket;
 pub mod cache;
 pub mod cat;
 pub mod checkup;
+#[cfg(feature = "download")]
+pub mod checkver;
+pub mod config;
 pub mod credits;
 pub mod debug;
 pub mod depends;
@@ -9,10 +14,14 @@ pub mod describe;
 #[cfg(feature = "download")]
 pub mod download;
 pub mod export;
+pub mod format;
 pub mod home;
 pub mod hook;
 pub mod info;
+#[cfg(feature = "download")]
+pub mod lint;
 pub mod list;
+mod manifests;
 #[cfg(not(feature = "v2"))]
 pub mod outdated;
 pub mod search;
@@ -23,7 +32,7 @@ pub mod virustotal;
 use clap::Subcommand;
 
 use sfsu_derive::{Hooks, Runnable};
-use sprinkles::{config, contexts::ScoopContext};
+use sprinkles::contexts::ScoopContext;
 
 use crate::{abandon, output::colours::eprintln_yellow};
 
@@ -52,9 +61,15 @@ pub trait Command {
         None
     }
 
-    async fn runner(self, ctx: &impl ScoopContext<config::Scoop>) -> Result<(), anyhow::Error>;
+    async fn runner(
+        self,
+        ctx: &impl ScoopContext<sprinkles::config::Scoop>,
+    ) -> Result<(), anyhow::Error>;
 
-    async fn run(self, ctx: &impl ScoopContext<config::Scoop>) -> Result<(), anyhow::Error>
+    async fn run(
+        self,
+        ctx: &impl ScoopContext<sprinkles::config::Scoop>,
+    ) -> Result<(), anyhow::Error>
     where
         Self: Sized,
     {
@@ -117,6 +132,21 @@ pub enum Commands {
     #[cfg(feature = "download")]
     /// Download the specified app.
     Download(download::Args),
+    #[no_hook]
+    #[cfg(feature = "download")]
+    /// Check for new upstream versions of an app, or every app in a bucket
+    Checkver(checkver::Args),
+    #[no_hook]
+    #[cfg(feature = "download")]
+    /// Update an app's manifest, or every manifest in a bucket, to a new version in place
+    Autoupdate(autoupdate::Args),
+    #[no_hook]
+    #[cfg(feature = "download")]
+    /// Check manifests for problems that would break installs or updates
+    Lint(lint::Args),
+    #[no_hook]
+    /// Format manifests into the canonical form used by Scoop buckets
+    Format(format::Args),
     /// Show status and check for new app versions
     Status(status::Args),
     #[cfg_attr(not(feature = "v2"), no_hook)]
@@ -130,6 +160,8 @@ pub enum Commands {
     Export(export::Args),
     /// Check for common issues
     Checkup(checkup::Args),
+    /// Get, set or remove Scoop config values
+    Config(config::Args),
     #[cfg(feature = "download")]
     /// Show or clear the download cache
     Cache(cache::Args),
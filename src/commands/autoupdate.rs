@@ -0,0 +1,146 @@
+use clap::Parser;
+use futures::StreamExt;
+use serde::Serialize;
+use sprinkles::{
+    checkver::{Checked, Checker},
+    config,
+    contexts::ScoopContext,
+    output::structured::Structured,
+    packages::Manifest,
+    version::Version,
+};
+
+use crate::abandon;
+
+use super::checkver::{target_manifests, CheckerArgs};
+
+/// The number of manifests to update at once
+const CONCURRENT_UPDATES: usize = 8;
+
+#[derive(Debug, Clone, Parser)]
+pub struct Args {
+    #[clap(help = "The app or bucket to update")]
+    target: String,
+
+    #[clap(
+        long,
+        help = "The version to update to. Defaults to the latest version found by checkver"
+    )]
+    version: Option<String>,
+
+    #[clap(
+        short,
+        long,
+        help = "Update the manifest even if it is already up to date"
+    )]
+    force: bool,
+
+    #[clap(
+        short = 'n',
+        long,
+        help = "Show what would be updated, without writing any manifests"
+    )]
+    dry_run: bool,
+
+    #[clap(flatten)]
+    checker: CheckerArgs,
+
+    #[clap(from_global)]
+    json: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AutoupdateInfo {
+    name: String,
+    bucket: String,
+    previous: String,
+    version: String,
+    status: String,
+}
+
+impl super::Command for Args {
+    async fn runner(self, ctx: &impl ScoopContext<config::Scoop>) -> Result<(), anyhow::Error> {
+        let manifests = target_manifests(ctx, &self.target, |manifest| {
+            manifest.autoupdate.is_some()
+                && (self.version.is_some() || manifest.install_config.checkver.is_some())
+        })
+        .await?;
+
+        if manifests.is_empty() {
+            abandon!(
+                "No manifests with autoupdate configurations found in {}",
+                self.target
+            );
+        }
+
+        let checker = self.checker.checker(ctx.config());
+
+        let mut outputs = futures::stream::iter(manifests)
+            .map(|manifest| self.update(ctx, &checker, manifest))
+            .buffer_unordered(CONCURRENT_UPDATES)
+            .collect::<Vec<_>>()
+            .await;
+
+        outputs.sort_by(|a, b| a.name.cmp(&b.name));
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&outputs)?);
+        } else {
+            print!("{}", Structured::new(&outputs).with_max_length(50));
+        }
+
+        Ok(())
+    }
+}
+
+impl Args {
+    async fn update(
+        &self,
+        ctx: &impl ScoopContext<config::Scoop>,
+        checker: &Checker,
+        mut manifest: Manifest,
+    ) -> AutoupdateInfo {
+        let previous = manifest.version.to_string();
+
+        let status = match self.update_manifest(ctx, checker, &mut manifest).await {
+            Ok(status) => status.to_string(),
+            Err(err) => err.to_string(),
+        };
+
+        AutoupdateInfo {
+            name: manifest.name,
+            bucket: manifest.bucket,
+            previous,
+            version: manifest.version.to_string(),
+            status,
+        }
+    }
+
+    async fn update_manifest(
+        &self,
+        ctx: &impl ScoopContext<config::Scoop>,
+        checker: &Checker,
+        manifest: &mut Manifest,
+    ) -> anyhow::Result<&'static str> {
+        let Checked { version, matches } = match &self.version {
+            Some(version) => Version::new(version).into(),
+            None => checker.check(manifest).await?,
+        };
+
+        if !self.force && version.compare(&manifest.version).is_le() {
+            return Ok("Up to date");
+        }
+
+        if self.dry_run {
+            manifest.version = version;
+            return Ok("Would update");
+        }
+
+        manifest
+            .autoupdate_to(ctx, version.to_string(), &matches)
+            .await?;
+        manifest.write_to_bucket(ctx)?;
+
+        Ok("Updated")
+    }
+}
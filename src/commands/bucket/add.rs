@@ -22,6 +22,10 @@ pub struct Args {
 
 impl super::Command for Args {
     async fn runner(self, ctx: &impl ScoopContext<config::Scoop>) -> anyhow::Result<()> {
+        if sprinkles::offline::enabled() {
+            abandon!("Cannot add buckets while offline");
+        }
+
         let repo_url = self
             .repo
             .clone()
@@ -56,6 +60,7 @@ impl super::Command for Args {
                 &repo_url,
                 dest_path,
                 sprinkles::git::clone::progress::Discard,
+                ctx.config(),
             )?;
 
             spinner.finish_with_message("✅ Repository cloned");
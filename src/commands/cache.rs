@@ -1,4 +1,4 @@
-use std::{os::windows::fs::MetadataExt, path::PathBuf};
+use std::{os::windows::fs::MetadataExt, path::PathBuf, time::SystemTime};
 
 use anyhow::Context;
 use clap::{Parser, Subcommand};
@@ -8,8 +8,11 @@ use sfsu_derive::Runnable;
 use sprinkles::{config, contexts::ScoopContext, output::wrappers::sizes::Size};
 use tokio::task::JoinSet;
 
+mod prune;
 mod rm;
 mod show;
+#[cfg(feature = "download")]
+mod verify;
 
 use crate::{abandon, commands::Command};
 
@@ -21,6 +24,8 @@ struct CacheEntry {
     version: String,
     size: Size,
     url: String,
+    #[serde(skip)]
+    modified: SystemTime,
 }
 
 impl CacheEntry {
@@ -66,6 +71,7 @@ impl CacheEntry {
                     version: version.to_string(),
                     url: url.to_string(),
                     size,
+                    modified: metadata.modified()?,
                 };
 
                 anyhow::Ok(cache_entry)
@@ -99,6 +105,11 @@ enum Commands {
     Show(show::Args),
     /// Remove cache entries
     Rm(rm::Args),
+    /// Remove cache entries that are no longer needed
+    Prune(prune::Args),
+    #[cfg(feature = "download")]
+    /// Check cache entries against the hashes in their manifests
+    Verify(verify::Args),
 }
 
 #[derive(Debug, Clone, Parser)]
@@ -0,0 +1,395 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+use clap::Parser;
+use serde::Serialize;
+use sprinkles::{
+    config,
+    contexts::ScoopContext,
+    output::{structured::Structured, wrappers::sizes::Size},
+    packages::MinInfo,
+};
+use tokio::task::JoinSet;
+
+use crate::{abandon, commands::Command, output::colours::eprintln_bright_yellow};
+
+use super::CacheEntry;
+
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
+#[derive(Debug, Clone, Parser)]
+pub struct Args {
+    #[clap(
+        long,
+        help = "Remove entries for installed apps, other than the currently installed version"
+    )]
+    keep_installed: bool,
+
+    #[clap(long, help = "Remove entries for apps that are not installed")]
+    uninstalled: bool,
+
+    #[clap(
+        long,
+        value_name = "DAYS",
+        help = "Remove entries older than the given number of days"
+    )]
+    older_than: Option<u64>,
+
+    #[clap(
+        long,
+        value_name = "SIZE",
+        help = "Remove the least recently used entries until the cache is at most the given size (e.g. 10GiB)"
+    )]
+    max_size: Option<Size>,
+
+    #[clap(
+        short = 'n',
+        long,
+        help = "Show what would be removed, without removing anything"
+    )]
+    dry_run: bool,
+
+    #[clap(from_global)]
+    apps: Vec<String>,
+
+    #[clap(from_global)]
+    json: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PruneInfo {
+    name: String,
+    version: String,
+    size: Size,
+    url: String,
+    reason: &'static str,
+}
+
+impl Command for Args {
+    async fn runner(self, ctx: &impl ScoopContext<config::Scoop>) -> Result<(), anyhow::Error> {
+        if !self.keep_installed
+            && !self.uninstalled
+            && self.older_than.is_none()
+            && self.max_size.is_none()
+        {
+            abandon!("No prune policies provided. See `sfsu cache prune --help` for options");
+        }
+
+        let cache_entries = CacheEntry::match_paths(ctx, &self.apps).await?;
+
+        let installed = MinInfo::list_installed(ctx, None)?
+            .into_iter()
+            .map(|info| (info.name, info.version))
+            .collect::<HashMap<_, _>>();
+
+        let pruned = self.prune(cache_entries, &installed, SystemTime::now());
+
+        let total_entries = pruned.len();
+        let total_size = pruned
+            .iter()
+            .fold(Size::new(0), |acc, (entry, _)| acc + entry.size);
+
+        if !self.dry_run {
+            let mut set = JoinSet::new();
+
+            for (entry, _) in &pruned {
+                let file_path = entry.file_path.clone();
+                set.spawn(async move { tokio::fs::remove_file(file_path).await });
+            }
+
+            while let Some(result) = set.join_next().await {
+                result??;
+            }
+        }
+
+        let outputs = pruned
+            .into_iter()
+            .map(|(entry, reason)| PruneInfo {
+                name: entry.name,
+                version: entry.version,
+                size: entry.size,
+                url: entry.url,
+                reason,
+            })
+            .collect::<Vec<_>>();
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&outputs)?);
+        } else if !outputs.is_empty() {
+            println!("{}", Structured::new(&outputs).with_max_length(50));
+        }
+
+        if self.dry_run {
+            eprintln_bright_yellow!("Would delete {total_entries} files, {total_size}");
+        } else {
+            eprintln_bright_yellow!("Deleted {total_entries} files, {total_size}");
+        }
+
+        Ok(())
+    }
+}
+
+impl Args {
+    /// Select the entries to remove, along with the reason they are removed
+    fn prune(
+        &self,
+        entries: Vec<CacheEntry>,
+        installed: &HashMap<String, String>,
+        now: SystemTime,
+    ) -> Vec<(CacheEntry, &'static str)> {
+        let mut pruned = vec![];
+        let mut kept = vec![];
+
+        // Partial downloads may still be in progress, so they are never pruned
+        let entries = entries.into_iter().filter(|entry| {
+            entry
+                .file_path
+                .extension()
+                .map_or(true, |extension| extension != "part")
+        });
+
+        for entry in entries {
+            match self.prune_reason(&entry, installed, now) {
+                Some(reason) => pruned.push((entry, reason)),
+                None => kept.push(entry),
+            }
+        }
+
+        if let Some(max_size) = self.max_size {
+            // Remove the least recently used entries first
+            kept.sort_by_key(|entry| entry.modified);
+
+            let mut total_size = kept.iter().map(|entry| entry.size.bytes()).sum::<u64>();
+
+            for entry in kept {
+                if total_size <= max_size.bytes() {
+                    break;
+                }
+
+                total_size -= entry.size.bytes();
+                pruned.push((entry, "Over size limit"));
+            }
+        }
+
+        pruned
+    }
+
+    fn prune_reason(
+        &self,
+        entry: &CacheEntry,
+        installed: &HashMap<String, String>,
+        now: SystemTime,
+    ) -> Option<&'static str> {
+        let installed_version = installed.get(&entry.name);
+
+        if self.uninstalled && installed_version.is_none() {
+            return Some("Not installed");
+        }
+
+        if self.keep_installed && installed_version.is_some_and(|version| version != &entry.version)
+        {
+            return Some("Not installed version");
+        }
+
+        if let Some(days) = self.older_than {
+            let age = now.duration_since(entry.modified).unwrap_or_default();
+
+            if age > Duration::from_secs(days.saturating_mul(SECONDS_PER_DAY)) {
+                return Some("Too old");
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn args() -> Args {
+        Args {
+            keep_installed: false,
+            uninstalled: false,
+            older_than: None,
+            max_size: None,
+            dry_run: false,
+            apps: vec![],
+            json: false,
+        }
+    }
+
+    fn entry(file_name: &str, size: u64, modified: SystemTime) -> CacheEntry {
+        let mut parts = file_name.split('#');
+
+        CacheEntry {
+            file_path: PathBuf::from(file_name),
+            name: parts.next().unwrap().to_string(),
+            version: parts.next().unwrap().to_string(),
+            size: Size::new(size),
+            url: parts.next().unwrap().to_string(),
+            modified,
+        }
+    }
+
+    fn days_ago(now: SystemTime, days: u64) -> SystemTime {
+        now - Duration::from_secs(days * SECONDS_PER_DAY)
+    }
+
+    fn pruned_files(pruned: &[(CacheEntry, &'static str)]) -> Vec<(String, &'static str)> {
+        pruned
+            .iter()
+            .map(|(entry, reason)| (entry.file_path.display().to_string(), *reason))
+            .collect()
+    }
+
+    fn installed() -> HashMap<String, String> {
+        HashMap::from([("sfsu".to_string(), "1.1.0".to_string())])
+    }
+
+    #[test]
+    fn test_prune_keep_installed() {
+        let now = SystemTime::now();
+        let entries = vec![
+            entry("sfsu#1.0.0#sfsu.zip", 10, now),
+            entry("sfsu#1.1.0#sfsu.zip", 10, now),
+            entry("git#2.0.0#git.7z", 10, now),
+        ];
+
+        let pruned = Args {
+            keep_installed: true,
+            ..args()
+        }
+        .prune(entries, &installed(), now);
+
+        assert_eq!(
+            pruned_files(&pruned),
+            vec![("sfsu#1.0.0#sfsu.zip".to_string(), "Not installed version")]
+        );
+    }
+
+    #[test]
+    fn test_prune_uninstalled() {
+        let now = SystemTime::now();
+        let entries = vec![
+            entry("sfsu#1.0.0#sfsu.zip", 10, now),
+            entry("git#2.0.0#git.7z", 10, now),
+        ];
+
+        let pruned = Args {
+            uninstalled: true,
+            ..args()
+        }
+        .prune(entries, &installed(), now);
+
+        assert_eq!(
+            pruned_files(&pruned),
+            vec![("git#2.0.0#git.7z".to_string(), "Not installed")]
+        );
+    }
+
+    #[test]
+    fn test_prune_older_than() {
+        let now = SystemTime::now();
+        let entries = vec![
+            entry("sfsu#1.0.0#sfsu.zip", 10, days_ago(now, 31)),
+            entry("sfsu#1.1.0#sfsu.zip", 10, days_ago(now, 29)),
+        ];
+
+        let pruned = Args {
+            older_than: Some(30),
+            ..args()
+        }
+        .prune(entries, &installed(), now);
+
+        assert_eq!(
+            pruned_files(&pruned),
+            vec![("sfsu#1.0.0#sfsu.zip".to_string(), "Too old")]
+        );
+    }
+
+    #[test]
+    fn test_prune_older_than_does_not_overflow() {
+        let now = SystemTime::now();
+        let entries = vec![entry("sfsu#1.0.0#sfsu.zip", 10, days_ago(now, 365))];
+
+        let pruned = Args {
+            older_than: Some(u64::MAX),
+            ..args()
+        }
+        .prune(entries, &installed(), now);
+
+        assert!(pruned.is_empty());
+    }
+
+    #[test]
+    fn test_prune_max_size_removes_least_recently_used() {
+        let now = SystemTime::now();
+        let entries = vec![
+            entry("sfsu#1.0.0#sfsu.zip", 40, days_ago(now, 3)),
+            entry("git#2.0.0#git.7z", 40, days_ago(now, 1)),
+            entry("sfsu#1.1.0#sfsu.zip", 40, days_ago(now, 2)),
+        ];
+
+        let pruned = Args {
+            max_size: Some(Size::new(50)),
+            ..args()
+        }
+        .prune(entries, &installed(), now);
+
+        assert_eq!(
+            pruned_files(&pruned),
+            vec![
+                ("sfsu#1.0.0#sfsu.zip".to_string(), "Over size limit"),
+                ("sfsu#1.1.0#sfsu.zip".to_string(), "Over size limit"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prune_max_size_counts_remaining_entries_only() {
+        let now = SystemTime::now();
+        let entries = vec![
+            entry("git#2.0.0#git.7z", 40, days_ago(now, 3)),
+            entry("sfsu#1.1.0#sfsu.zip", 40, days_ago(now, 2)),
+        ];
+
+        let pruned = Args {
+            uninstalled: true,
+            max_size: Some(Size::new(40)),
+            ..args()
+        }
+        .prune(entries, &installed(), now);
+
+        assert_eq!(
+            pruned_files(&pruned),
+            vec![("git#2.0.0#git.7z".to_string(), "Not installed")]
+        );
+    }
+
+    #[test]
+    fn test_prune_skips_partial_downloads() {
+        let now = SystemTime::now();
+        let entries = vec![
+            entry("git#2.0.0#git.7z.part", 40, days_ago(now, 90)),
+            entry("git#2.0.0#git.7z", 40, days_ago(now, 90)),
+        ];
+
+        let pruned = Args {
+            uninstalled: true,
+            older_than: Some(30),
+            max_size: Some(Size::new(0)),
+            ..args()
+        }
+        .prune(entries, &installed(), now);
+
+        assert_eq!(
+            pruned_files(&pruned),
+            vec![("git#2.0.0#git.7z".to_string(), "Not installed")]
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use clap::Parser;
+use futures::StreamExt;
+use serde::Serialize;
+use sprinkles::{
+    cache::Handle,
+    config,
+    contexts::ScoopContext,
+    output::structured::Structured,
+    packages::{reference::ManifestRef, CreateManifest, Manifest},
+    Architecture,
+};
+
+use crate::commands::Command;
+
+use super::CacheEntry;
+
+/// The number of cache entries to hash at once
+const CONCURRENT_CHECKS: usize = 4;
+
+#[derive(Debug, Clone, Parser)]
+pub struct Args {
+    #[clap(
+        long,
+        help = "Remove cache entries that do not match their manifest hash"
+    )]
+    remove: bool,
+
+    #[clap(from_global)]
+    apps: Vec<String>,
+
+    #[clap(from_global)]
+    json: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct VerifyInfo {
+    name: String,
+    version: String,
+    url: String,
+    status: String,
+}
+
+impl Command for Args {
+    async fn runner(self, ctx: &impl ScoopContext<config::Scoop>) -> Result<(), anyhow::Error> {
+        let cache_entries = CacheEntry::match_paths(ctx, &self.apps).await?;
+
+        let outputs = futures::stream::iter(cache_entries)
+            // Partial downloads are verified once they are complete
+            .filter(|entry| {
+                futures::future::ready(
+                    entry
+                        .file_path
+                        .extension()
+                        .map_or(true, |extension| extension != "part"),
+                )
+            })
+            .map(|entry| self.verify(ctx, entry))
+            .buffered(CONCURRENT_CHECKS)
+            .collect::<Vec<_>>()
+            .await;
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&outputs)?);
+        } else if !outputs.is_empty() {
+            print!("{}", Structured::new(&outputs).with_max_length(50));
+        }
+
+        let invalid = outputs
+            .iter()
+            .filter(|output| output.status == "Invalid")
+            .count();
+        if invalid > 0 {
+            anyhow::bail!("{invalid} cache entries do not match their manifest hash");
+        }
+
+        Ok(())
+    }
+}
+
+impl Args {
+    async fn verify(
+        &self,
+        ctx: &impl ScoopContext<config::Scoop>,
+        entry: CacheEntry,
+    ) -> VerifyInfo {
+        let status = match self.verify_entry(ctx, &entry).await {
+            Ok(status) => status.to_string(),
+            Err(err) => err.to_string(),
+        };
+
+        VerifyInfo {
+            name: entry.name,
+            version: entry.version,
+            url: entry.url,
+            status,
+        }
+    }
+
+    async fn verify_entry(
+        &self,
+        ctx: &impl ScoopContext<config::Scoop>,
+        entry: &CacheEntry,
+    ) -> anyhow::Result<&'static str> {
+        let Some(handle) = find_handle(ctx, entry).await? else {
+            return Ok("No manifest");
+        };
+
+        let Some(computed_hash) = handle.cached_hash().await? else {
+            return Ok("Missing");
+        };
+
+        if handle
+            .actual_hash()
+            .is_some_and(|actual_hash| computed_hash.matches(actual_hash))
+        {
+            Ok("Valid")
+        } else if self.remove {
+            tokio::fs::remove_file(&entry.file_path).await?;
+            Ok("Removed")
+        } else {
+            Ok("Invalid")
+        }
+    }
+}
+
+/// Find the cache handle for the entry, from the installed manifest or any bucket manifest with the same version
+async fn find_handle(
+    ctx: &impl ScoopContext<config::Scoop>,
+    entry: &CacheEntry,
+) -> anyhow::Result<Option<Handle>> {
+    let file_name = entry.file_path.file_name();
+
+    let installed = ctx
+        .apps_path()
+        .join(&entry.name)
+        .join(&entry.version)
+        .join("manifest.json");
+
+    let mut manifests = if installed.exists() {
+        let mut manifest = Manifest::from_path(installed)?;
+        manifest.name.clone_from(&entry.name);
+
+        vec![manifest]
+    } else {
+        vec![]
+    };
+
+    manifests.extend(
+        ManifestRef::Name(entry.name.clone())
+            .into_package_ref()
+            .list_manifests(ctx)
+            .await?,
+    );
+
+    for manifest in manifests
+        .iter()
+        .filter(|manifest| manifest.version.to_string() == entry.version)
+    {
+        for arch in Architecture::VARIANTS {
+            let Ok(handles) = Handle::open_manifest(ctx.cache_path(), manifest, arch) else {
+                continue;
+            };
+
+            if let Some(handle) = handles
+                .into_iter()
+                .find(|handle| Some(handle.file_name.as_os_str()) == file_name)
+            {
+                return Ok(Some(handle));
+            }
+        }
+    }
+
+    Ok(None)
+}
@@ -4,7 +4,7 @@ use itertools::Itertools;
 use sprinkles::{
     config,
     contexts::ScoopContext,
-    diagnostics::{Diagnostics, LongPathsStatus},
+    diagnostics::{Check, Diagnostics, LongPathsStatus},
 };
 
 #[derive(Debug, Clone, Parser)]
@@ -52,25 +52,35 @@ impl super::Command for Args {
         //     );
         // }
 
-        if diagnostics.windows_developer {
+        if !diagnostics.is_supported(Check::WindowsDeveloper) {
+            println!("❓ Windows Developer Mode could not be checked on this platform");
+        } else if diagnostics.windows_developer {
             println!("✅ Windows Developer Mode is enabled");
         } else {
             println!("❌ Windows Developer Mode is not enabled");
             println!("\tWindows Developer Mode is not enabled. Operations relevant to symlinks may fail without proper rights");
         }
 
-        match diagnostics.long_paths {
-            LongPathsStatus::Enabled => println!("✅ Long paths are enabled"),
-            LongPathsStatus::OldWindows => {
-                println!("❌ This version of Windows does not support long paths");
-            }
-            LongPathsStatus::Disabled => {
-                println!("❌ Long paths are disabled");
-                println!("\tRun `Set-ItemProperty 'HKLM:\\SYSTEM\\CurrentControlSet\\Control\\FileSystem' -Name 'LongPathsEnabled' -Value 1` as admin to enable it");
+        if diagnostics.is_supported(Check::LongPaths) {
+            match diagnostics.long_paths {
+                LongPathsStatus::Enabled => println!("✅ Long paths are enabled"),
+                LongPathsStatus::OldWindows => {
+                    println!("❌ This version of Windows does not support long paths");
+                }
+                LongPathsStatus::Disabled => {
+                    println!("❌ Long paths are disabled");
+                    println!("\tRun `Set-ItemProperty 'HKLM:\\SYSTEM\\CurrentControlSet\\Control\\FileSystem' -Name 'LongPathsEnabled' -Value 1` as admin to enable it");
+                }
             }
+        } else {
+            println!("❓ Long paths could not be checked on this platform");
         }
 
-        if diagnostics.scoop_ntfs {
+        if !diagnostics.is_supported(Check::ScoopNtfs) {
+            println!(
+                "❓ The filesystem of the Scoop directory could not be checked on this platform"
+            );
+        } else if diagnostics.scoop_ntfs {
             println!("✅ NTFS is the filesystem of the Scoop directory");
         } else {
             println!("❌ NTFS is not the filesystem of the Scoop directory");
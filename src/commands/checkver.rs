@@ -0,0 +1,139 @@
+use clap::Parser;
+use futures::StreamExt;
+use serde::Serialize;
+use sprinkles::{
+    buckets::Bucket,
+    checkver::{Checker, GITHUB_API_URL, SOURCEFORGE_URL},
+    config,
+    contexts::ScoopContext,
+    output::structured::Structured,
+    packages::{reference::Package, Manifest},
+    requests::{self, AsyncClient, ClientLike},
+};
+
+use crate::abandon;
+
+/// The number of manifests to check at once
+const CONCURRENT_CHECKS: usize = 16;
+
+#[derive(Debug, Clone, Parser)]
+pub struct Args {
+    #[clap(help = "The app or bucket to check for new versions")]
+    target: String,
+
+    #[clap(flatten)]
+    checker: CheckerArgs,
+
+    #[clap(from_global)]
+    json: bool,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct CheckerArgs {
+    #[clap(
+        long,
+        env = "SFSU_GITHUB_API_URL",
+        default_value = GITHUB_API_URL,
+        help = "The base url of the GitHub API"
+    )]
+    github_api_url: String,
+
+    #[clap(
+        long,
+        env = "SFSU_SOURCEFORGE_URL",
+        default_value = SOURCEFORGE_URL,
+        help = "The base url of SourceForge"
+    )]
+    sourceforge_url: String,
+}
+
+impl CheckerArgs {
+    pub fn checker(&self, config: &config::Scoop) -> Checker {
+        Checker::new()
+            .with_client(AsyncClient::with_config(config).client().clone())
+            .with_github_api_url(&self.github_api_url)
+            .with_github_token(requests::github_token(Some(config)))
+            .with_sourceforge_url(&self.sourceforge_url)
+    }
+}
+
+/// Get the manifest for the target app, or every manifest in the target bucket that matches the filter
+pub async fn target_manifests(
+    ctx: &impl ScoopContext<config::Scoop>,
+    target: &str,
+    filter: impl Fn(&Manifest) -> bool,
+) -> anyhow::Result<Vec<Manifest>> {
+    if let Ok(bucket) = Bucket::from_name(ctx, target) {
+        Ok(bucket
+            .list_packages_unchecked()?
+            .into_iter()
+            .filter(filter)
+            .collect())
+    } else {
+        let package: Package = target.parse()?;
+        Ok(vec![package.manifest(ctx).await?])
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct CheckverInfo {
+    name: String,
+    bucket: String,
+    current: String,
+    latest: String,
+    status: String,
+}
+
+impl super::Command for Args {
+    async fn runner(self, ctx: &impl ScoopContext<config::Scoop>) -> Result<(), anyhow::Error> {
+        let manifests = target_manifests(ctx, &self.target, |manifest| {
+            manifest.install_config.checkver.is_some()
+        })
+        .await?;
+
+        if manifests.is_empty() {
+            abandon!(
+                "No manifests with checkver configurations found in {}",
+                self.target
+            );
+        }
+
+        let checker = self.checker.checker(ctx.config());
+
+        let mut outputs = futures::stream::iter(manifests)
+            .map(|manifest| Self::check(&checker, manifest))
+            .buffer_unordered(CONCURRENT_CHECKS)
+            .collect::<Vec<_>>()
+            .await;
+
+        outputs.sort_by(|a, b| a.name.cmp(&b.name));
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&outputs)?);
+        } else {
+            print!("{}", Structured::new(&outputs).with_max_length(50));
+        }
+
+        Ok(())
+    }
+}
+
+impl Args {
+    async fn check(checker: &Checker, manifest: Manifest) -> CheckverInfo {
+        let (latest, status) = match checker.check(&manifest).await {
+            Ok(latest) if latest.version.compare(&manifest.version).is_gt() => {
+                (latest.version.to_string(), "Outdated".to_string())
+            }
+            Ok(latest) => (latest.version.to_string(), "Up to date".to_string()),
+            Err(err) => (String::new(), err.to_string()),
+        };
+
+        CheckverInfo {
+            name: manifest.name,
+            bucket: manifest.bucket,
+            current: manifest.version.to_string(),
+            latest,
+            status,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use clap::Parser;
+use serde_json::{json, Value};
+use sprinkles::{config, contexts::ScoopContext, output::structured::vertical::VTable};
+
+use crate::output::colours::eprintln_yellow;
+
+#[derive(Debug, Clone, Parser)]
+pub struct Args {
+    #[clap(help = "The config key to get, set or remove. Lists the whole config if not provided")]
+    key: Option<String>,
+
+    #[clap(help = "The value to set the key to")]
+    value: Option<String>,
+
+    #[clap(
+        long,
+        help = "Remove the key from the config",
+        requires = "key",
+        conflicts_with = "value"
+    )]
+    rm: bool,
+
+    #[clap(
+        long,
+        help = "Show which config layer set each value",
+        conflicts_with_all = ["value", "rm"]
+    )]
+    show_origin: bool,
+
+    #[clap(from_global)]
+    json: bool,
+}
+
+impl super::Command for Args {
+    async fn runner(self, _: &impl ScoopContext<config::Scoop>) -> anyhow::Result<()> {
+        let Some(key) = self.key else {
+            let layered = config::Scoop::load_layered()?;
+            let map = layered.config().to_map()?;
+
+            if self.show_origin {
+                if self.json {
+                    let origins = map
+                        .into_iter()
+                        .map(|(key, value)| {
+                            let origin = layered.origin(&key).to_string();
+                            (key, json!({ "value": value, "origin": origin }))
+                        })
+                        .collect::<serde_json::Map<_, _>>();
+
+                    println!("{}", serde_json::to_string_pretty(&origins)?);
+                } else {
+                    for (key, value) in map {
+                        println!("{}\t{key}={}", layered.origin(&key), display(&value));
+                    }
+                }
+            } else if self.json {
+                println!("{}", serde_json::to_string_pretty(&map)?);
+            } else {
+                println!("{}", VTable::new(&Value::Object(map)));
+            }
+
+            return Ok(());
+        };
+
+        if self.rm {
+            // Only the user config is modified, so values from other layers are not saved into it
+            let mut config = load_user_config()?;
+
+            if config.unset(&key)?.is_some() {
+                config.save()?;
+                println!("'{key}' has been removed");
+            } else {
+                eprintln_yellow!("'{key}' is not set in the user config");
+            }
+        } else if let Some(value) = self.value {
+            let mut config = load_user_config()?;
+
+            config.set(&key, &value)?;
+            config.save()?;
+            println!("'{key}' has been set to '{value}'");
+        } else {
+            let layered = config::Scoop::load_layered()?;
+            let value = layered.config().get(&key)?;
+            let origin = layered.origin(&key);
+
+            if self.json {
+                let value = value.unwrap_or(Value::Null);
+                let output = if self.show_origin {
+                    json!({ "value": value, "origin": origin.to_string() })
+                } else {
+                    value
+                };
+
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            } else if let Some(value) = value {
+                if self.show_origin {
+                    println!("{origin}\t{}", display(&value));
+                } else {
+                    println!("{}", display(&value));
+                }
+            } else {
+                eprintln_yellow!("'{key}' is not set");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Display a config value, without quotes around strings
+fn display(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Load the user config, starting from the defaults if the user config file does not exist yet
+///
+/// The other layers may still set values, so a missing user config is not an error
+fn load_user_config() -> std::io::Result<config::Scoop> {
+    match config::Scoop::load() {
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(config::Scoop::default()),
+        result => result,
+    }
+}
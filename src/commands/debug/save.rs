@@ -5,8 +5,8 @@ use sprinkles::{config, contexts::ScoopContext};
 pub struct Args {}
 
 impl super::Command for Args {
-    async fn runner(self, ctx: &impl ScoopContext<config::Scoop>) -> anyhow::Result<()> {
-        ctx.config().save()?;
+    async fn runner(self, _: &impl ScoopContext<config::Scoop>) -> anyhow::Result<()> {
+        config::Scoop::load()?.save()?;
 
         Ok(())
     }
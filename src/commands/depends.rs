@@ -1,55 +1,121 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use sprinkles::{
     config,
     contexts::ScoopContext,
     output::sectioned::{Children, Section, Sections},
-    packages::reference::{self, Package},
+    packages::{
+        dependencies::{Graph, ReverseIndex},
+        reference::Package,
+    },
 };
 
 use crate::abandon;
 
 #[derive(Debug, Clone, Parser)]
 pub struct Args {
-    #[clap(help = "The package to list dependencies for")]
-    package: Package,
+    #[clap(
+        help = "The package to list dependencies for",
+        required_unless_present = "graph"
+    )]
+    package: Option<Package>,
 
     #[clap(help = "The bucket of the given package")]
     bucket: Option<String>,
 
-    // TODO: Implement recursion?
-    // recursive: bool,
+    #[clap(
+        short,
+        long,
+        help = "List the installed packages that depend on the given package, including transitive dependents"
+    )]
+    reverse: bool,
+
+    #[clap(
+        short,
+        long,
+        conflicts_with = "reverse",
+        help = "Render the dependency graph of the given package, or of every installed package if none is given"
+    )]
+    graph: Option<GraphFormat>,
+
     #[clap(from_global)]
     json: bool,
 }
 
+#[derive(Debug, Copy, Clone, ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+}
+
 impl super::Command for Args {
     async fn runner(mut self, ctx: &impl ScoopContext<config::Scoop>) -> Result<(), anyhow::Error> {
-        if let Some(bucket) = self.bucket {
-            self.package.set_bucket(bucket)?;
+        let mut package = self.package.take();
+        if let (Some(package), Some(bucket)) = (package.as_mut(), self.bucket.take()) {
+            package.set_bucket(bucket)?;
+        }
+
+        if let Some(format) = self.graph {
+            let graph = match package {
+                Some(package) => Graph::resolve(ctx, [package.manifest(ctx).await?]).await?,
+                None => Graph::installed(ctx).await?,
+            };
+
+            match format {
+                GraphFormat::Dot => println!("{}", graph.to_dot()),
+                GraphFormat::Mermaid => print!("{}", graph.to_mermaid()),
+            }
+
+            return Ok(());
         }
 
-        let manifests = self.package.list_manifests(ctx).await?;
+        let package = package.expect("package to be required without a graph format");
+
+        if self.reverse {
+            return self.list_dependents(ctx, &package);
+        }
+
+        let manifests = package.list_manifests(ctx).await?;
 
         if manifests.is_empty() {
-            abandon!("Could not find any packages matching: {}", self.package);
+            abandon!("Could not find any packages matching: {}", package);
         };
 
+        let mut graphs = vec![];
+        for manifest in manifests {
+            let title = format!(
+                "Dependencies for '{}' in '{}'",
+                manifest.name, manifest.bucket
+            );
+
+            graphs.push((title, Graph::resolve(ctx, [manifest]).await?));
+        }
+
         if self.json {
-            println!("{}", serde_json::to_string(&manifests)?);
+            let graphs = graphs
+                .into_iter()
+                .map(|(_, graph)| graph)
+                .collect::<Vec<_>>();
+            println!("{}", serde_json::to_string(&graphs)?);
             return Ok(());
         }
 
-        let output: Sections<reference::ManifestRef> = manifests
+        let output: Sections<String> = graphs
             .into_iter()
-            .filter_map(|manifest| {
-                Children::from(manifest.depends())
-                    .into_option()
-                    .map(|children| {
-                        Section::new(children).with_title(format!(
-                            "Dependencies for '{}' in '{}'",
-                            manifest.name, manifest.bucket
-                        ))
+            .filter_map(|(title, graph)| {
+                graph
+                    .dependencies()
+                    .map(|node| {
+                        if node.installed {
+                            format!("{} (installed)", node.reference())
+                        } else {
+                            node.reference().to_string()
+                        }
                     })
+                    .collect::<Children<_>>()
+                    .into_option()
+                    .map(|children| Section::new(children).with_title(title))
             })
             .collect();
 
@@ -59,4 +125,40 @@ impl super::Command for Args {
     }
 }
 
+impl Args {
+    fn list_dependents(
+        &self,
+        ctx: &impl ScoopContext<config::Scoop>,
+        package: &Package,
+    ) -> anyhow::Result<()> {
+        let dependents = ReverseIndex::load(ctx)?.dependents(package);
+
+        if self.json {
+            println!("{}", serde_json::to_string(&dependents)?);
+            return Ok(());
+        }
+
+        let children = dependents
+            .into_iter()
+            .map(|dependent| {
+                if dependent.direct {
+                    dependent.reference.to_string()
+                } else {
+                    format!("{} (transitive)", dependent.reference)
+                }
+            })
+            .collect::<Children<_>>();
+
+        let output = match children.into_option() {
+            Some(children) => Sections::from_vec(vec![Section::new(children)
+                .with_title(format!("Installed packages depending on '{package}'"))]),
+            None => Sections::from_vec(vec![]),
+        };
+
+        println!("{output}");
+
+        Ok(())
+    }
+}
+
 // note to self, use `phpstudy-lagecy-scoop` to test this command
@@ -2,9 +2,10 @@ use clap::Parser;
 use indicatif::MultiProgress;
 
 use sprinkles::{
-    cache::{Downloader, Handle},
+    cache::{limits::DownloadLimits, DownloadResult, Downloader, Handle},
     config,
     contexts::ScoopContext,
+    output::wrappers::sizes::Size,
     packages::reference::Package,
     requests::AsyncClient,
     Architecture,
@@ -21,6 +22,21 @@ pub struct Args {
     #[clap(short = 'H', long, help = "Disable hash validation")]
     no_hash_check: bool,
 
+    #[clap(
+        short = 'j',
+        long,
+        value_name = "COUNT",
+        help = "The maximum number of files to download at once. Defaults to the `download_concurrency` config value"
+    )]
+    max_downloads: Option<usize>,
+
+    #[clap(
+        long,
+        value_name = "SIZE",
+        help = "The maximum combined download speed per second (e.g. 2MiB). Defaults to the `download_bandwidth_limit` config value"
+    )]
+    limit_rate: Option<Size>,
+
     #[clap(help = "The packages to download")]
     packages: Vec<Package>,
 
@@ -44,58 +60,64 @@ impl super::Command for Args {
 
         let mp = MultiProgress::new();
 
+        let config = ctx.config();
+        let limits = DownloadLimits::new(
+            self.max_downloads.or_else(|| {
+                config
+                    .download_concurrency
+                    .and_then(|limit| usize::try_from(limit).ok())
+            }),
+            self.limit_rate
+                .map(Size::bytes)
+                .or(config.download_bandwidth_limit),
+        );
+
         eprint!("Attempting to generate manifest(s)");
-        let downloaders: Vec<Downloader> =
-            futures::future::try_join_all(self.packages.into_iter().map(|package| {
-                let mp = mp.clone();
-                async move {
-                    let manifest = match package.manifest(ctx).await {
-                        Ok(manifest) => manifest,
-                        Err(e) => abandon!("\rFailed to generate manifest: {e}"),
-                    };
-
-                    let dl = Handle::open_manifest(ctx.cache_path(), &manifest, self.arch)?;
-
-                    let downloaders = dl.into_iter().map(|dl| {
-                        let mp = mp.clone();
-                        async move {
-                            match Downloader::new::<AsyncClient>(dl, Some(&mp)).await {
-                                Ok(dl) => anyhow::Ok(dl),
-                                Err(e) => match e {
-                                    sprinkles::cache::Error::ErrorCode(status) => {
-                                        abandon!("Found {status} error while downloading")
-                                    }
-                                    _ => Err(e.into()),
-                                },
-                            }
-                        }
-                    });
-                    let downloaders = futures::future::try_join_all(downloaders).await?;
-
-                    anyhow::Ok(downloaders)
-                }
+        let handles: Vec<Handle> =
+            futures::future::try_join_all(self.packages.into_iter().map(|package| async move {
+                let manifest = match package.manifest(ctx).await {
+                    Ok(manifest) => manifest,
+                    Err(e) => abandon!("\rFailed to generate manifest: {e}"),
+                };
+
+                let handles = Handle::open_manifest(ctx.cache_path(), &manifest, self.arch)?;
+
+                anyhow::Ok(handles)
             }))
             .await?
             .into_iter()
             .flatten()
+            .map(|handle| {
+                handle
+                    .with_config(config)
+                    .with_limits(limits.clone())
+                    .with_hash_check(!self.no_hash_check)
+            })
             .collect();
         eprintln!("\r📜 Generated manifest for any and all mismatched versions");
 
-        let threads = downloaders
-            .into_iter()
-            .map(|dl| tokio::spawn(async move { dl.download().await }));
+        // Downloads wait for the limits before making any requests,
+        // so each one is started and finished by the same task
+        let threads = handles.into_iter().map(|handle| {
+            let mp = mp.clone();
+
+            tokio::spawn(async move { download(handle, Some(&mp)).await })
+        });
 
         let results = futures::future::try_join_all(threads).await?;
 
         for result in results {
-            let result = result?;
+            let result = match result {
+                Ok(result) => result,
+                Err(e) => abandon!("{e}"),
+            };
 
             if !self.no_hash_check {
                 eprint!("🔓 Checking {} hash...", result.file_name.url);
 
                 let actual_hash = result.actual_hash.no_prefix();
 
-                if result.actual_hash == result.computed_hash {
+                if result.actual_hash.matches(&result.computed_hash) {
                     eprintln!("\r🔒 Hash matched: {actual_hash}");
                 } else {
                     eprintln!();
@@ -116,3 +138,69 @@ impl super::Command for Args {
         Ok(())
     }
 }
+
+/// Download a single file, or use it from the cache if it is already cached
+///
+/// While offline, only cached files can be used
+async fn download(handle: Handle, mp: Option<&MultiProgress>) -> anyhow::Result<DownloadResult> {
+    let file_name = handle.file_name.clone();
+
+    let dl = match Downloader::new::<AsyncClient>(handle, mp).await {
+        Ok(dl) => dl,
+        Err(sprinkles::cache::Error::ErrorCode(status)) => {
+            anyhow::bail!("Found {status} error while downloading")
+        }
+        Err(sprinkles::cache::Error::Offline) => anyhow::bail!(
+            "{} is not in the cache, and cannot be downloaded while offline",
+            file_name.display()
+        ),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(dl.download().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use sprinkles::{
+        contexts::Fixture,
+        hash::{Hash, HashType},
+    };
+
+    use super::*;
+
+    fn handle(ctx: &impl ScoopContext<config::Scoop>, file_name: &str) -> Handle {
+        Handle::new(
+            ctx.cache_path(),
+            format!("app#1.0.0#{file_name}"),
+            HashType::SHA256,
+            format!("https://example.invalid/{file_name}"),
+            Hash::from_hex(&[0; 32]),
+        )
+        .unwrap()
+        .with_hash_check(false)
+    }
+
+    #[tokio::test]
+    async fn test_download_offline() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = Fixture::builder(dir.path())
+            .cache_file("app#1.0.0#cached.zip", "cached")
+            .build()
+            .unwrap();
+
+        // No other test in this crate uses the network, so enabling offline mode for the whole process is fine
+        sprinkles::offline::set(true);
+
+        let cached = download(handle(&ctx, "cached.zip"), None).await;
+        let missing = download(handle(&ctx, "missing.zip"), None).await;
+
+        sprinkles::offline::set(false);
+
+        assert_eq!(cached.unwrap().file_name.url, "cached.zip");
+        assert_eq!(
+            missing.unwrap_err().to_string(),
+            "app#1.0.0#missing.zip is not in the cache, and cannot be downloaded while offline"
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use rayon::prelude::*;
+use serde::Serialize;
+use sprinkles::{config, contexts::ScoopContext, packages::format};
+
+use crate::abandon;
+
+use super::manifests::manifest_paths;
+
+#[derive(Debug, Clone, Parser)]
+pub struct Args {
+    #[clap(help = "The bucket, bucket directory or manifest file to format")]
+    target: String,
+
+    #[clap(
+        long,
+        help = "Only check if the manifests are formatted, without writing any changes"
+    )]
+    check: bool,
+
+    #[clap(from_global)]
+    json: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct FormatInfo {
+    path: PathBuf,
+    changed: bool,
+}
+
+impl super::Command for Args {
+    async fn runner(self, ctx: &impl ScoopContext<config::Scoop>) -> Result<(), anyhow::Error> {
+        let paths = manifest_paths(ctx, &self.target)?;
+
+        if paths.is_empty() {
+            abandon!("No manifests found in {}", self.target);
+        }
+
+        let mut outputs = paths
+            .into_par_iter()
+            .map(|path| -> anyhow::Result<FormatInfo> {
+                let contents = std::fs::read_to_string(&path)?;
+                let formatted = format::format(&contents)
+                    .map_err(|err| anyhow::anyhow!("{}: {err}", path.display()))?;
+
+                let changed = formatted != contents;
+
+                if changed && !self.check {
+                    std::fs::write(&path, formatted)?;
+                }
+
+                Ok(FormatInfo { path, changed })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        outputs.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let changed = outputs.iter().filter(|output| output.changed).count();
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&outputs)?);
+        } else {
+            for output in outputs.iter().filter(|output| output.changed) {
+                if self.check {
+                    println!("Would format {}", output.path.display());
+                } else {
+                    println!("Formatted {}", output.path.display());
+                }
+            }
+        }
+
+        if self.check && changed > 0 {
+            anyhow::bail!("{changed} manifest(s) are not formatted");
+        }
+
+        Ok(())
+    }
+}
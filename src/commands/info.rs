@@ -15,10 +15,10 @@ use sprinkles::{
         },
         reference, Manifest, MergeDefaults,
     },
-    semver, Architecture,
+    Architecture,
 };
 
-use crate::abandon;
+use crate::{abandon, output::colours::eprintln_yellow};
 
 #[derive(Debug, Clone, Parser)]
 #[allow(clippy::struct_excessive_bools)]
@@ -47,6 +47,9 @@ pub struct Args {
     #[clap(from_global)]
     disable_git: bool,
 
+    #[clap(from_global)]
+    offline: bool,
+
     #[clap(long, help = "Disable updated info")]
     disable_updated: bool,
 }
@@ -74,18 +77,19 @@ impl super::Command for Args {
             );
         }
 
+        if self.offline && !self.json {
+            eprintln_yellow!(
+                "Showing information from the local buckets, which may be out of date while offline"
+            );
+        }
+
         let installed_apps = ctx.installed_apps()?;
 
         if self.single {
             let latest = manifests
                 .into_iter()
-                .max_by(|a_manifest, b_manifest| {
-                    semver::Version::try_from(&a_manifest.version)
-                        .and_then(|a_version| {
-                            Ok(a_version.cmp(&semver::Version::try_from(&b_manifest.version)?))
-                        })
-                        .unwrap_or(std::cmp::Ordering::Equal)
-                }).expect("something went terribly wrong (no manifests found even though we just checked for manifests)");
+                .max_by(|a_manifest, b_manifest| a_manifest.version.cmp(&b_manifest.version))
+                .expect("something went terribly wrong (no manifests found even though we just checked for manifests)");
 
             self.print_manifest(ctx, latest, &installed_apps, Architecture::ARCH)?;
         } else {
@@ -0,0 +1,97 @@
+use clap::Parser;
+use rayon::prelude::*;
+use sprinkles::{
+    config,
+    contexts::ScoopContext,
+    packages::lint::{Report, Severity},
+};
+
+use crate::abandon;
+
+use super::manifests::manifest_paths;
+
+#[derive(Debug, Clone, Parser)]
+pub struct Args {
+    #[clap(help = "The bucket, bucket directory or manifest file to lint")]
+    target: String,
+
+    #[clap(
+        long,
+        conflicts_with = "json",
+        help = "Print lints as GitHub Actions annotations"
+    )]
+    github: bool,
+
+    #[clap(from_global)]
+    json: bool,
+}
+
+impl super::Command for Args {
+    async fn runner(self, ctx: &impl ScoopContext<config::Scoop>) -> Result<(), anyhow::Error> {
+        let paths = manifest_paths(ctx, &self.target)?;
+
+        if paths.is_empty() {
+            abandon!("No manifests found in {}", self.target);
+        }
+
+        let mut reports = paths
+            .par_iter()
+            .map(|path| Report::lint_file(ctx, path))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        reports.sort_by(|a, b| a.name.cmp(&b.name));
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&reports)?);
+        } else if self.github {
+            for report in &reports {
+                for lint in &report.lints {
+                    println!(
+                        "::{} file={},title={}::{}",
+                        match lint.severity {
+                            Severity::Error => "error",
+                            Severity::Warning => "warning",
+                        },
+                        escape_property(&report.path.display().to_string()),
+                        escape_property(&format!("sfsu lint ({})", lint.rule)),
+                        escape_data(&lint.message),
+                    );
+                }
+            }
+        } else {
+            for report in reports.iter().filter(|report| !report.lints.is_empty()) {
+                println!("{}", console::style(&report.name).bold());
+
+                for lint in &report.lints {
+                    let severity = match lint.severity {
+                        Severity::Error => console::style(lint.severity).red(),
+                        Severity::Warning => console::style(lint.severity).yellow(),
+                    };
+
+                    println!("  {severity} [{}]: {}", lint.rule, lint.message);
+                }
+            }
+        }
+
+        let errors = reports.iter().filter(|report| report.has_errors()).count();
+        if errors > 0 {
+            anyhow::bail!("{errors} manifest(s) failed linting");
+        }
+
+        Ok(())
+    }
+}
+
+/// Escape a GitHub Actions workflow command message
+fn escape_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a GitHub Actions workflow command property
+fn escape_property(property: &str) -> String {
+    escape_data(property)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
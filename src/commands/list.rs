@@ -3,6 +3,7 @@ use rayon::prelude::*;
 
 use sprinkles::{
     config, contexts::ScoopContext, output::structured::Structured, packages::MinInfo,
+    version::Version,
 };
 
 #[derive(Debug, Clone, Parser)]
@@ -41,7 +42,7 @@ impl super::Command for Args {
 
         outputs.par_sort_by(|a, b| match self.sort_by {
             SortBy::Name => a.name.cmp(&b.name),
-            SortBy::Version => a.version.cmp(&b.version),
+            SortBy::Version => Version::new(&a.version).cmp(&Version::new(&b.version)),
             SortBy::Source => a.source.cmp(&b.source),
             SortBy::Updated => a.updated.cmp(&b.updated),
             SortBy::Notes => a.notes.cmp(&b.notes),
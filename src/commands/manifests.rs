@@ -0,0 +1,37 @@
+//! Helpers shared by the commands that operate on manifest files
+
+use std::path::{Path, PathBuf};
+
+use sprinkles::{buckets::Bucket, config, contexts::ScoopContext};
+
+/// Find the manifests for a bucket name, bucket directory, or a single manifest file
+pub fn manifest_paths(
+    ctx: &impl ScoopContext<config::Scoop>,
+    target: &str,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let path = Path::new(target);
+
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let directory = if path.is_dir() {
+        // Buckets keep their manifests in the `bucket` directory, if it exists
+        let manifests = path.join("bucket");
+        if manifests.is_dir() {
+            manifests
+        } else {
+            path.to_path_buf()
+        }
+    } else {
+        Bucket::from_name(ctx, target)?.path().join("bucket")
+    };
+
+    Ok(std::fs::read_dir(directory)?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect())
+}
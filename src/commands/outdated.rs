@@ -21,6 +21,12 @@ pub struct Args {
     #[command(subcommand)]
     command: Option<Commands>,
 
+    #[clap(
+        long,
+        help = "Check every bucket for the newest version of each app, instead of only the bucket it was installed from"
+    )]
+    all_buckets: bool,
+
     #[clap(from_global)]
     json: bool,
 }
@@ -40,9 +46,12 @@ impl Command for Args {
             if self.json {
                 let mut map = Map::new();
 
-                let apps = apps::Args { json: self.json }
-                    .run_direct(ctx, false)?
-                    .unwrap_or_default();
+                let apps = apps::Args {
+                    all_buckets: self.all_buckets,
+                    json: self.json,
+                }
+                .run_direct(ctx, false)?
+                .unwrap_or_default();
 
                 let buckets = buckets::Args { json: self.json }
                     .run_direct(ctx, false)?
@@ -56,9 +65,12 @@ impl Command for Args {
                 println!("{output}");
             } else {
                 println!("Outdated Apps:");
-                Commands::Apps(apps::Args { json: self.json })
-                    .run(ctx)
-                    .await?;
+                Commands::Apps(apps::Args {
+                    all_buckets: self.all_buckets,
+                    json: self.json,
+                })
+                .run(ctx)
+                .await?;
                 println!("\nOutdated Buckets:");
                 Commands::Buckets(buckets::Args { json: self.json })
                     .run(ctx)
@@ -11,6 +11,12 @@ use sprinkles::{
 
 #[derive(Debug, Clone, Parser)]
 pub struct Args {
+    #[clap(
+        long,
+        help = "Check every bucket for the newest version, instead of only the bucket each app was installed from"
+    )]
+    pub(super) all_buckets: bool,
+
     #[clap(from_global)]
     pub(super) json: bool,
 }
@@ -38,9 +44,22 @@ impl Args {
         let mut outdated: Vec<outdated::Info> = apps
             .par_iter()
             .flat_map(|app| -> anyhow::Result<outdated::Info> {
-                if let Some(bucket) = &app.bucket {
+                if self.all_buckets {
+                    let local_manifest = app.get_manifest(ctx)?;
+
+                    let Some(remote_manifest) = Bucket::newest_manifest(ctx, &app.name)? else {
+                        anyhow::bail!("no manifest found in any bucket")
+                    };
+
+                    if let Some(info) =
+                        outdated::Info::from_newer_manifest(&local_manifest, &remote_manifest)
+                    {
+                        Ok(info)
+                    } else {
+                        anyhow::bail!("no update available")
+                    }
+                } else if let Some(bucket) = &app.bucket {
                     let local_manifest = app.get_manifest(ctx)?;
-                    // TODO: Add the option to check all buckets and find the highest version (will require semver to order versions)
                     let bucket = Bucket::from_name(ctx, bucket)?;
 
                     let remote_manifest = bucket.get_manifest(&app.name)?;
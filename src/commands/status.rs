@@ -33,11 +33,20 @@ pub struct Args {
     #[clap(from_global)]
     verbose: bool,
 
+    #[clap(from_global)]
+    offline: bool,
+
     #[clap(short = 'O', long, help = "Only check the provided sections of Scoop")]
     only: Vec<Command>,
 
     #[clap(short = 'H', long, help = "Ignore held packages")]
     ignore_held: bool,
+
+    #[clap(
+        long,
+        help = "Check every bucket for the newest version, instead of only the bucket each app was installed from"
+    )]
+    all_buckets: bool,
 }
 
 impl super::Command for Args {
@@ -98,6 +107,21 @@ impl Args {
         value: &Mutex<Value>,
         output: &mut dyn Write,
     ) -> anyhow::Result<()> {
+        if self.offline {
+            // Checking for updates requires connecting to the remote repository
+            if self.json {
+                value.lock()["scoop"] = Value::Null;
+            } else {
+                writeln!(
+                    output,
+                    "{}",
+                    console::style("Skipped checking for Scoop updates while offline").yellow()
+                )?;
+            }
+
+            return Ok(());
+        }
+
         let is_outdated = ctx.outdated().await?;
 
         if self.json {
@@ -125,6 +149,20 @@ impl Args {
         value: &Mutex<Value>,
         output: &mut dyn Write,
     ) -> anyhow::Result<()> {
+        if self.offline {
+            if self.json {
+                value.lock()["buckets"] = Value::Null;
+            } else {
+                writeln!(
+                    output,
+                    "{}",
+                    console::style("Skipped checking for bucket updates while offline").yellow()
+                )?;
+            }
+
+            return Ok(());
+        }
+
         let buckets = Bucket::list_all(ctx)?;
 
         // Handle buckets
@@ -199,9 +237,26 @@ impl Args {
         let mut invalid_apps = apps
             .par_iter()
             .flat_map(|app| -> anyhow::Result<Info> {
-                if let Some(bucket) = &app.bucket {
+                if self.all_buckets {
+                    let local_manifest = app.get_manifest(ctx)?;
+
+                    let Some(remote_manifest) = Bucket::newest_manifest(ctx, &app.name)? else {
+                        error!("{} was not found in any bucket", app.name);
+                        anyhow::bail!("{} was not found in any bucket", app.name)
+                    };
+
+                    match Info::from_remote_manifest(ctx, &local_manifest, &remote_manifest) {
+                        Ok(info) => Ok(Info {
+                            bucket: Some(remote_manifest.bucket),
+                            ..info
+                        }),
+                        Err(err) => {
+                            error!("Failed to get status for {}: {:?}", app.name, err);
+                            anyhow::bail!("Failed to get status for {}: {:?}", app.name, err)
+                        }
+                    }
+                } else if let Some(bucket) = &app.bucket {
                     let local_manifest = app.get_manifest(ctx)?;
-                    // TODO: Add the option to check all buckets and find the highest version (will require semver to order versions)
                     let bucket = Bucket::from_name(ctx, bucket)?;
 
                     match Info::from_manifests(ctx, &local_manifest, &bucket) {
@@ -230,7 +285,7 @@ impl Args {
                 };
 
                 // Filter out apps that are okay
-                info_exists || missing_deps || app.current != app.available
+                info_exists || missing_deps || app.is_outdated()
             })
             .collect::<Vec<_>>();
 
@@ -241,6 +296,17 @@ impl Args {
             return Ok(());
         }
 
+        if self.offline {
+            writeln!(
+                output,
+                "{}",
+                console::style(
+                    "Packages were compared against the local buckets, which may be out of date"
+                )
+                .yellow()
+            )?;
+        }
+
         if invalid_apps.is_empty() {
             writeln!(output, "All packages are okay and up to date.")?;
         } else {
@@ -283,3 +349,91 @@ impl Args {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use sprinkles::{contexts::Fixture, packages::InstallManifest};
+
+    use super::*;
+
+    fn offline_args(json: bool) -> Args {
+        Args {
+            json,
+            verbose: false,
+            offline: true,
+            only: vec![],
+            ignore_held: false,
+            all_buckets: false,
+        }
+    }
+
+    fn fixture(path: &std::path::Path) -> impl ScoopContext<config::Scoop> {
+        Fixture::builder(path)
+            .bucket_manifest("main", "sfsu", r#"{"version":"2.0.0"}"#)
+            .app(
+                "sfsu",
+                "1.0.0",
+                r#"{"version":"1.0.0"}"#,
+                &InstallManifest {
+                    bucket: Some("main".into()),
+                    ..Default::default()
+                },
+            )
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_status_offline_skips_remote_checks() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = fixture(dir.path());
+        let args = offline_args(false);
+        let value = Mutex::new(Value::default());
+
+        let mut output = String::new();
+        args.handle_scoop(&ctx, &value, &mut output).await.unwrap();
+        args.handle_buckets(&ctx, &value, &mut output).unwrap();
+
+        assert!(output.contains("Skipped checking for Scoop updates while offline"));
+        assert!(output.contains("Skipped checking for bucket updates while offline"));
+    }
+
+    #[tokio::test]
+    async fn test_status_offline_json_reports_unknown_updates() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = fixture(dir.path());
+        let args = offline_args(true);
+        let value = Mutex::new(serde_json::json!({ "scoop": true, "buckets": [] }));
+
+        let mut output = String::new();
+        args.handle_scoop(&ctx, &value, &mut output).await.unwrap();
+        args.handle_buckets(&ctx, &value, &mut output).unwrap();
+
+        assert!(output.is_empty());
+        assert_eq!(value.lock()["scoop"], Value::Null);
+        assert_eq!(value.lock()["buckets"], Value::Null);
+    }
+
+    #[test]
+    fn test_status_offline_compares_local_buckets() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = fixture(dir.path());
+        let value = Mutex::new(Value::default());
+
+        let mut output = String::new();
+        offline_args(false)
+            .handle_packages(&ctx, &value, &mut output)
+            .unwrap();
+
+        assert!(output.contains(
+            "Packages were compared against the local buckets, which may be out of date"
+        ));
+        assert!(output.contains("sfsu"));
+
+        offline_args(true)
+            .handle_packages(&ctx, &value, &mut String::new())
+            .unwrap();
+
+        assert_eq!(value.lock()["packages"][0]["name"], "sfsu");
+    }
+}
@@ -93,6 +93,14 @@ struct Args {
     )]
     disable_git: bool,
 
+    #[clap(
+        long,
+        global = true,
+        help = "Avoid accessing the network. Anything that requires the network will fail, or use local information instead",
+        env = "SFSU_OFFLINE"
+    )]
+    offline: bool,
+
     #[cfg(feature = "contexts")]
     #[clap(short, long, global = true, help = "Use the global Scoop context")]
     global: bool,
@@ -101,13 +109,15 @@ struct Args {
 pub(crate) static COLOR_ENABLED: AtomicBool = AtomicBool::new(true);
 
 #[cfg(feature = "contexts")]
-impl From<&Args> for AnyContext {
-    fn from(args: &Args) -> Self {
-        if args.global {
-            AnyContext::Global(sprinkles::contexts::Global::new())
+impl TryFrom<&Args> for AnyContext {
+    type Error = sprinkles::contexts::Error;
+
+    fn try_from(args: &Args) -> Result<Self, Self::Error> {
+        Ok(if args.global {
+            AnyContext::Global(sprinkles::contexts::Global::try_new()?)
         } else {
-            AnyContext::User(User::new())
-        }
+            AnyContext::User(User::try_new()?)
+        })
     }
 }
 
@@ -117,15 +127,18 @@ async fn main() -> anyhow::Result<()> {
 
     let args = Args::parse();
 
-    let ctx: AnyContext = {
+    sprinkles::offline::set(args.offline);
+
+    let ctx: Result<AnyContext, _> = {
         cfg_if::cfg_if! {
             if #[cfg(feature = "contexts")] {
-                (&args).into()
+                (&args).try_into()
             } else {
-                AnyContext::User(User::new())
+                User::try_new().map(AnyContext::User)
             }
         }
     };
+    let ctx = ctx.unwrap_or_else(|e| abandon!("{e}"));
 
     // Spawn a task to cleanup logs in the background
     tokio::task::spawn_blocking({